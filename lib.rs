@@ -1,215 +1,8385 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use ink_lang as ink;
+use ink_prelude::{string::String, vec::Vec};
 
+type AccountId = <ink_env::DefaultEnvironment as ink_env::Environment>::AccountId;
+type Balance = <ink_env::DefaultEnvironment as ink_env::Environment>::Balance;
+
+/// The error types of the PSP22 standard.
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22Error {
+	/// Any error that has no standard variant.
+	Custom(String),
+	InsufficientBalance,
+	InsufficientAllowance,
+	ZeroRecipientAddress,
+	ZeroSenderAddress,
+	SafeTransferCheckFailed(String),
+}
+
+/// The PSP22 fungible token standard.
+#[ink::trait_definition]
+pub trait PSP22 {
+	#[ink(message)]
+	fn total_supply(&self) -> Balance;
+
+	#[ink(message)]
+	fn balance_of(&self, owner: AccountId) -> Balance;
+
+	#[ink(message)]
+	fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance;
+
+	#[ink(message)]
+	fn transfer(&mut self, to: AccountId, value: Balance, data: Vec<u8>) -> Result<(), PSP22Error>;
+
+	#[ink(message)]
+	fn transfer_from(
+		&mut self,
+		from: AccountId,
+		to: AccountId,
+		value: Balance,
+		data: Vec<u8>,
+	) -> Result<(), PSP22Error>;
+
+	#[ink(message)]
+	fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), PSP22Error>;
+
+	#[ink(message)]
+	fn increase_allowance(&mut self, spender: AccountId, delta_value: Balance) -> Result<(), PSP22Error>;
+
+	#[ink(message)]
+	fn decrease_allowance(&mut self, spender: AccountId, delta_value: Balance) -> Result<(), PSP22Error>;
+}
+
+/// The error a PSP22 receiver returns to refuse incoming tokens.
+#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22ReceiverError {
+	TransferRejected(String),
+}
+
+/// Implemented by contracts that want to vet PSP22 tokens sent to them.
+#[ink::trait_definition]
+pub trait PSP22Receiver {
+	/// Called before `value` tokens from `from` are credited; an error refuses them.
+	#[ink(message)]
+	fn before_received(
+		&mut self,
+		operator: AccountId,
+		from: AccountId,
+		value: Balance,
+		data: Vec<u8>,
+	) -> Result<(), PSP22ReceiverError>;
+}
+
+/// The optional metadata extension of PSP22.
+#[ink::trait_definition]
+pub trait PSP22Metadata {
+	#[ink(message)]
+	fn token_name(&self) -> Option<String>;
+
+	#[ink(message)]
+	fn token_symbol(&self) -> Option<String>;
+
+	#[ink(message)]
+	fn token_decimals(&self) -> u8;
+}
+
+/// Ready-made cross-contract calls to the PSP22 messages of any token, for contracts that only hold the
+/// token's `AccountId`; `fire()` sends them. `Erc20Ref` offers the same when the token is this contract.
+pub mod psp22_calls {
+	use super::{AccountId, Balance, PSP22Error};
+	use ink_env::call::{
+		build_call,
+		utils::{ArgsList, EmptyArgumentList, ReturnType, Set},
+		Call, CallBuilder, ExecutionInput, Selector,
+	};
+	use ink_prelude::vec::Vec;
+
+	type Environment = ink_env::DefaultEnvironment;
+
+	/// A call to a token returning `R`, with its callee and input set.
+	pub type Psp22Call<Args, R> = CallBuilder<Environment, Set<Call<Environment>>, Set<ExecutionInput<Args>>, Set<ReturnType<R>>>;
+
+	pub type BalanceOfArgs = ArgsList<AccountId, EmptyArgumentList>;
+	pub type TransferArgs = ArgsList<Vec<u8>, ArgsList<Balance, ArgsList<AccountId, EmptyArgumentList>>>;
+	pub type TransferFromArgs = ArgsList<Vec<u8>, ArgsList<Balance, ArgsList<AccountId, ArgsList<AccountId, EmptyArgumentList>>>>;
+
+	pub fn balance_of_input(owner: AccountId) -> ExecutionInput<BalanceOfArgs> {
+		ExecutionInput::new(Selector::new(ink_lang::selector_bytes!("PSP22::balance_of"))).push_arg(owner)
+	}
+
+	pub fn transfer_input(to: AccountId, value: Balance, data: Vec<u8>) -> ExecutionInput<TransferArgs> {
+		ExecutionInput::new(Selector::new(ink_lang::selector_bytes!("PSP22::transfer")))
+			.push_arg(to)
+			.push_arg(value)
+			.push_arg(data)
+	}
+
+	pub fn transfer_from_input(from: AccountId, to: AccountId, value: Balance, data: Vec<u8>) -> ExecutionInput<TransferFromArgs> {
+		ExecutionInput::new(Selector::new(ink_lang::selector_bytes!("PSP22::transfer_from")))
+			.push_arg(from)
+			.push_arg(to)
+			.push_arg(value)
+			.push_arg(data)
+	}
+
+	pub fn balance_of(token: AccountId, owner: AccountId) -> Psp22Call<BalanceOfArgs, Balance> {
+		build_call::<Environment>()
+			.call_type(Call::new().callee(token))
+			.exec_input(balance_of_input(owner))
+			.returns::<Balance>()
+	}
+
+	pub fn transfer(token: AccountId, to: AccountId, value: Balance, data: Vec<u8>) -> Psp22Call<TransferArgs, Result<(), PSP22Error>> {
+		build_call::<Environment>()
+			.call_type(Call::new().callee(token))
+			.exec_input(transfer_input(to, value, data))
+			.returns::<Result<(), PSP22Error>>()
+	}
+
+	/// Needs an allowance from `from` to the calling contract.
+	pub fn transfer_from(
+		token: AccountId,
+		from: AccountId,
+		to: AccountId,
+		value: Balance,
+		data: Vec<u8>,
+	) -> Psp22Call<TransferFromArgs, Result<(), PSP22Error>> {
+		build_call::<Environment>()
+			.call_type(Call::new().callee(token))
+			.exec_input(transfer_from_input(from, to, value, data))
+			.returns::<Result<(), PSP22Error>>()
+	}
+}
+
+#[cfg(not(feature = "factory"))]
+pub use self::erc20::{Erc20, Erc20Ref, Error};
+
+#[cfg(not(feature = "factory"))]
 #[ink::contract]
 mod erc20 {
+	use super::{PSP22Error, PSP22Metadata, PSP22};
 	use ink_prelude::{
         string::String,
+        vec,
+        vec::Vec,
     };
-	use ink_storage::{traits::SpreadAllocate, Mapping};
+	#[cfg(not(test))]
+	use ink_env::call::{build_call, Call, ExecutionInput, Selector};
+	use ink_storage::{
+		traits::{ExtKeyPtr, KeyPtr, PackedLayout, SpreadAllocate, SpreadLayout},
+		Mapping,
+	};
+	#[cfg(feature = "std")]
+	use ink_metadata::layout::{FieldLayout, Layout, StructLayout};
+	#[cfg(feature = "std")]
+	use ink_storage::traits::StorageLayout;
+
+	#[ink(event)]
+	pub struct Transferred {
+		#[ink(topic)]
+		from: AccountId,
+		#[ink(topic)]
+		to: AccountId,
+		value: Balance,
+	}
+
+	#[ink(event)]
+	pub struct Minted {
+		#[ink(topic)]
+		to: AccountId,
+		value: Balance,
+	}
+
+	#[ink(event)]
+	pub struct Burned {
+		#[ink(topic)]
+		from: AccountId,
+		value: Balance,
+	}
+
+	#[ink(event)]
+	pub struct Approval {
+		#[ink(topic)]
+		owner: AccountId,
+		#[ink(topic)]
+		spender: AccountId,
+		value: Balance,
+	}
+
+	#[ink(event)]
+	pub struct OwnershipTransferStarted {
+		previous_owner: AccountId,
+		new_owner: AccountId,
+	}
+
+	/// `new_owner` is `None` when ownership was renounced.
+	#[ink(event)]
+	pub struct OwnershipTransferred {
+		previous_owner: AccountId,
+		new_owner: Option<AccountId>,
+	}
+
+	#[ink(event)]
+	pub struct RoleGranted {
+		role: RoleId,
+		account: AccountId,
+		sender: AccountId,
+	}
+
+	#[ink(event)]
+	pub struct RoleRevoked {
+		role: RoleId,
+		account: AccountId,
+		sender: AccountId,
+	}
+
+	#[ink(event)]
+	pub struct Paused {
+		account: AccountId,
+	}
+
+	#[ink(event)]
+	pub struct Unpaused {
+		account: AccountId,
+	}
+
+	#[ink(event)]
+	pub struct DelayedTransferQueued {
+		id: u64,
+		from: AccountId,
+		to: AccountId,
+		value: Balance,
+		executable_at: Timestamp,
+	}
+
+	#[ink(event)]
+	pub struct BridgeLocked {
+		from: AccountId,
+		amount: Balance,
+		dest_chain: u32,
+		dest_account: Vec<u8>,
+	}
+
+	#[ink(event)]
+	pub struct BridgeUnlocked {
+		to: AccountId,
+		amount: Balance,
+		src_chain: u32,
+		nonce: u64,
+	}
+
+	#[ink(event)]
+	pub struct MetadataUpdated {
+		name: String,
+		symbol: String,
+		metadata_uri: String,
+	}
+
+	#[ink(event)]
+	pub struct OperatorSet {
+		#[ink(topic)]
+		owner: AccountId,
+		#[ink(topic)]
+		operator: AccountId,
+		approved: bool,
+	}
+
+	#[ink(event)]
+	pub struct RecurringApproval {
+		#[ink(topic)]
+		owner: AccountId,
+		#[ink(topic)]
+		spender: AccountId,
+		amount_per_period: Balance,
+		period_blocks: BlockNumber,
+	}
+
+	#[ink(event)]
+	pub struct TokenUriUpdated {
+		token_uri: String,
+	}
+
+	#[ink(event)]
+	pub struct Snapshot {
+		id: SnapshotId,
+	}
+
+	#[ink(event)]
+	pub struct DelegateChanged {
+		#[ink(topic)]
+		delegator: AccountId,
+		from_delegate: Option<AccountId>,
+		to_delegate: Option<AccountId>,
+	}
+
+	#[ink(event)]
+	pub struct DelegateVotesChanged {
+		#[ink(topic)]
+		delegate: AccountId,
+		previous_votes: Balance,
+		new_votes: Balance,
+	}
+
+	#[ink(event)]
+	pub struct VestingScheduleCreated {
+		#[ink(topic)]
+		beneficiary: AccountId,
+		start: Timestamp,
+		cliff: Timestamp,
+		duration: Timestamp,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct VestingReleased {
+		#[ink(topic)]
+		beneficiary: AccountId,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct VestingRevoked {
+		#[ink(topic)]
+		beneficiary: AccountId,
+		released: Balance,
+		refunded: Balance,
+	}
+
+	#[ink(event)]
+	pub struct TokensLocked {
+		#[ink(topic)]
+		from: AccountId,
+		#[ink(topic)]
+		to: AccountId,
+		amount: Balance,
+		unlock_at: Timestamp,
+	}
+
+	#[ink(event)]
+	pub struct UnlockedTokensClaimed {
+		#[ink(topic)]
+		account: AccountId,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct FeeCollected {
+		#[ink(topic)]
+		from: AccountId,
+		#[ink(topic)]
+		collector: AccountId,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct Staked {
+		#[ink(topic)]
+		account: AccountId,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct Unstaked {
+		#[ink(topic)]
+		account: AccountId,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct RewardPaid {
+		#[ink(topic)]
+		account: AccountId,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct RewardsFunded {
+		amount: Balance,
+		duration: BlockNumber,
+	}
+
+	#[ink(event)]
+	pub struct AirdropClaimed {
+		index: u64,
+		#[ink(topic)]
+		account: AccountId,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct TokensPurchased {
+		#[ink(topic)]
+		buyer: AccountId,
+		paid: Balance,
+		amount: Balance,
+	}
+
+	/// Stranded funds sent back out; `token` is `None` for native currency.
+	#[ink(event)]
+	pub struct Rescued {
+		#[ink(topic)]
+		token: Option<AccountId>,
+		#[ink(topic)]
+		to: AccountId,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct FlashLoan {
+		#[ink(topic)]
+		receiver: AccountId,
+		amount: Balance,
+		fee: Balance,
+	}
+
+	#[ink(event)]
+	pub struct AccountFrozen {
+		#[ink(topic)]
+		account: AccountId,
+	}
+
+	#[ink(event)]
+	pub struct AccountUnfrozen {
+		#[ink(topic)]
+		account: AccountId,
+	}
+
+	#[ink(event)]
+	pub struct TradingEnabled {
+		block: BlockNumber,
+	}
+
+	#[ink(event)]
+	pub struct AdminActionProposed {
+		#[ink(topic)]
+		id: u64,
+		proposer: AccountId,
+		action: AdminAction,
+	}
+
+	#[ink(event)]
+	pub struct AdminActionApproved {
+		#[ink(topic)]
+		id: u64,
+		signer: AccountId,
+	}
+
+	#[ink(event)]
+	pub struct AdminActionExecuted {
+		#[ink(topic)]
+		id: u64,
+	}
+
+	#[ink(event)]
+	pub struct OperationQueued {
+		#[ink(topic)]
+		id: u64,
+		operation: AdminAction,
+		executable_at: BlockNumber,
+	}
+
+	#[ink(event)]
+	pub struct OperationExecuted {
+		#[ink(topic)]
+		id: u64,
+	}
+
+	#[ink(event)]
+	pub struct OperationCancelled {
+		#[ink(topic)]
+		id: u64,
+	}
+
+	#[ink(event)]
+	pub struct Upgraded {
+		code_hash: Hash,
+	}
+
+	#[ink(event)]
+	pub struct BridgeMinted {
+		#[ink(topic)]
+		bridge: AccountId,
+		#[ink(topic)]
+		to: AccountId,
+		amount: Balance,
+		src_chain: u32,
+		src_tx: Hash,
+	}
+
+	#[ink(event)]
+	pub struct SwapCreated {
+		#[ink(topic)]
+		id: u64,
+		#[ink(topic)]
+		sender: AccountId,
+		#[ink(topic)]
+		recipient: AccountId,
+		amount: Balance,
+		hashlock: Hash,
+		timelock: Timestamp,
+	}
+
+	/// Carries the preimage so the counterparty can claim the other leg of the swap.
+	#[ink(event)]
+	pub struct SwapClaimed {
+		#[ink(topic)]
+		id: u64,
+		preimage: Vec<u8>,
+	}
+
+	#[ink(event)]
+	pub struct SwapRefunded {
+		#[ink(topic)]
+		id: u64,
+	}
+
+	#[ink(event)]
+	pub struct StreamCreated {
+		#[ink(topic)]
+		id: u64,
+		#[ink(topic)]
+		sender: AccountId,
+		#[ink(topic)]
+		recipient: AccountId,
+		deposit: Balance,
+		start: Timestamp,
+		stop: Timestamp,
+	}
+
+	#[ink(event)]
+	pub struct WithdrawFromStream {
+		#[ink(topic)]
+		id: u64,
+		recipient: AccountId,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct StreamCancelled {
+		#[ink(topic)]
+		id: u64,
+		sender_balance: Balance,
+		recipient_balance: Balance,
+	}
+
+	#[ink(event)]
+	pub struct EscrowCreated {
+		#[ink(topic)]
+		id: u64,
+		#[ink(topic)]
+		buyer: AccountId,
+		#[ink(topic)]
+		seller: AccountId,
+		arbiter: AccountId,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct EscrowReleased {
+		#[ink(topic)]
+		id: u64,
+		by: AccountId,
+	}
+
+	#[ink(event)]
+	pub struct EscrowRefunded {
+		#[ink(topic)]
+		id: u64,
+		by: AccountId,
+	}
+
+	#[ink(event)]
+	pub struct EscrowDisputed {
+		#[ink(topic)]
+		id: u64,
+		by: AccountId,
+	}
+
+	#[ink(event)]
+	pub struct DividendsDistributed {
+		#[ink(topic)]
+		from: AccountId,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct DividendWithdrawn {
+		#[ink(topic)]
+		account: AccountId,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct Rebase {
+		#[ink(topic)]
+		epoch: u64,
+		total_supply: Balance,
+	}
+
+	#[ink(event)]
+	pub struct CurveBought {
+		#[ink(topic)]
+		buyer: AccountId,
+		paid: Balance,
+		amount: Balance,
+	}
+
+	#[ink(event)]
+	pub struct CurveSold {
+		#[ink(topic)]
+		seller: AccountId,
+		amount: Balance,
+		received: Balance,
+	}
+
+	#[ink(event)]
+	pub struct BridgeBurned {
+		#[ink(topic)]
+		bridge: AccountId,
+		#[ink(topic)]
+		from: AccountId,
+		amount: Balance,
+		dst_chain: u32,
+		dst_address: Vec<u8>,
+	}
+
+	#[ink(event)]
+	pub struct LockRejected {
+		#[ink(topic)]
+		from: AccountId,
+		#[ink(topic)]
+		to: AccountId,
+		amount: Balance,
+	}
+
+	/// The error types returned by the contract's messages.
+	#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub enum Error {
+		/// The account's balance does not cover the amount.
+		InsufficientBalance,
+		/// The spender's allowance does not cover the amount.
+		InsufficientAllowance,
+		/// The caller is not the owner, or ownership is renounced.
+		NotOwner,
+		/// Only the pending owner may accept ownership.
+		NotPendingOwner,
+		/// The caller lacks the role the message requires.
+		MissingRole,
+		/// Token movement is halted.
+		Paused,
+		/// The contract is not paused.
+		NotPaused,
+		/// The mint is larger than the per-transaction mint limit.
+		MintTooLarge,
+		/// The mint would push total supply past the cap.
+		CapExceeded,
+		/// Balance math would overflow or underflow.
+		ArithmeticOverflow,
+		/// Storage is from an older code version and `migrate` has not run.
+		MigrationPending,
+		/// Storage is already at the current version.
+		AlreadyMigrated,
+		/// More reads were batched than `MAX_READ_CALLS`.
+		TooManyReadCalls,
+		/// More calls were batched than `MAX_MULTICALL_CALLS`.
+		TooManyCalls,
+		/// A `multicall` payload names a message that cannot be batched.
+		UnsupportedCall,
+		/// A `multicall` payload's arguments do not decode for its message.
+		InvalidCallData,
+		/// The metadata URI is longer than `MAX_METADATA_URI_LEN`.
+		MetadataUriTooLong,
+		SwapOfferNotFound,
+		/// Only the named counterparty may accept a swap offer.
+		NotSwapCounterparty,
+		/// Only the maker may cancel a swap offer.
+		NotSwapMaker,
+		/// The counterparty's `transfer_from` on the wanted token failed.
+		SwapPaymentFailed,
+		DelayedTransferNotFound,
+		/// The delay of a queued transfer has not passed yet.
+		DelayedTransferNotReady,
+		/// Only the sender may cancel a queued transfer.
+		NotDelayedTransferSender,
+		/// A redirected burn was requested but no burn redirect pool is set.
+		NoBurnRedirect,
+		/// A balance root needs at least one holder.
+		NoHolders,
+		/// Holders must be sorted by account without duplicates.
+		HoldersNotOrdered,
+		/// Every holder in a balance root must have a balance.
+		HolderWithoutBalance,
+		/// The `(src_chain, nonce)` pair was already unlocked.
+		BridgeNonceUsed,
+		/// The caller already has a conditional transfer for the condition id.
+		ConditionInUse,
+		ConditionalTransferNotFound,
+		/// Only the sender may reclaim a conditional transfer.
+		NotConditionalTransferSender,
+		/// The conditional transfer can only be reclaimed after it expires.
+		ConditionalTransferNotExpired,
+		/// The call to the resolver's `is_met` failed.
+		ResolverCallFailed,
+		/// The signed permit's deadline has passed.
+		PermitExpired,
+		/// The signature does not recover to the expected signer.
+		InvalidSignature,
+		/// The transfer authorization's `valid_after` has not passed yet.
+		AuthorizationNotYetValid,
+		/// The transfer authorization's `valid_before` has passed.
+		AuthorizationExpired,
+		/// The authorizer already used this authorization nonce.
+		AuthorizationUsed,
+		/// No snapshot with this id has been taken.
+		SnapshotNotFound,
+		/// Past votes can only be read for blocks before the current one.
+		BlockNotYetMined,
+		/// The beneficiary already has a vesting schedule.
+		VestingScheduleExists,
+		/// The beneficiary has no vesting schedule.
+		NoVestingSchedule,
+		/// A vesting schedule needs a non-zero duration and a cliff no longer than it.
+		InvalidVestingSchedule,
+		/// Nothing has vested since the last release.
+		NothingToRelease,
+		/// The recipient already has `MAX_LOCKS_PER_ACCOUNT` pending time locks.
+		TooManyLocks,
+		/// None of the caller's time locks has matured.
+		NothingUnlocked,
+		/// The transfer fee is above `MAX_FEE_BPS`.
+		FeeTooHigh,
+		/// The transfer burn rate is above `MAX_BURN_RATE_BPS`.
+		BurnRateTooHigh,
+		/// The caller has staked less than it tried to unstake.
+		InsufficientStake,
+		/// The caller has no staking rewards to claim.
+		NoRewards,
+		/// A reward period must last at least one block.
+		InvalidRewardDuration,
+		/// No airdrop merkle root has been set.
+		NoMerkleRoot,
+		/// This airdrop index was already claimed.
+		AlreadyClaimed,
+		/// The proof does not lead from the claimed leaf to the airdrop merkle root.
+		InvalidProof,
+		/// The sale rate is zero, so `buy` is closed.
+		SaleClosed,
+		/// `buy` was called without enough native value to buy a single unit.
+		ZeroPurchase,
+		/// Sending native currency out of the contract failed.
+		NativeTransferFailed,
+		/// `deposit` and `withdraw` only exist on a token created with `new_wrapped`.
+		NotWrapped,
+		/// A wrapped token cannot mint beyond the native currency the contract holds.
+		Unbacked,
+		/// The call to the flash loan receiver's `on_flash_loan` failed.
+		FlashLoanCallbackFailed,
+		/// The flash loan receiver's `on_flash_loan` did not accept the loan.
+		FlashLoanRejected,
+		/// The receiving contract refused the tokens, or could not be asked.
+		SafeTransferCheckFailed(String),
+		/// A guarded message was re-entered from one of its own callbacks.
+		ReentrancyDetected,
+		/// The sender or recipient is frozen.
+		AccountFrozen,
+		/// The external rules contract restricted the transfer with this code.
+		TransferRestricted(u8),
+		/// Allowlist mode is on and the sender or recipient is not allowlisted.
+		NotAllowlisted,
+		/// The amount is above the per-transfer maximum.
+		MaxTxExceeded,
+		/// The transfer would take the recipient above the maximum wallet balance.
+		MaxWalletExceeded,
+		/// Trading has not been enabled yet and the sender is not exempt.
+		TradingNotEnabled,
+		/// The sender transferred too recently.
+		CooldownActive,
+		/// The caller is not one of the admin multisig signers.
+		NotAdminSigner,
+		/// Signers are empty or repeated, or the threshold is zero or above the signer count.
+		InvalidMultisig,
+		AdminActionNotFound,
+		/// The caller already approved this action.
+		AlreadyApproved,
+		/// Fewer signers than the threshold approved this action.
+		ThresholdNotReached,
+		/// A timelock delay is set, so the call must go through `queue_operation`.
+		TimelockRequired,
+		OperationNotFound,
+		/// The operation's timelock delay has not passed yet.
+		OperationNotReady,
+		/// `set_code_hash` failed, e.g. no code is uploaded under the hash.
+		UpgradeFailed,
+		/// The contract still holds tokens for escrows, vesting, streams or stakes, or native currency backing
+		/// the wrapped supply or curve.
+		OutstandingCommitments,
+		/// The mint would exceed what may be minted in the current period.
+		MintLimitExceeded,
+		/// A mint limit needs a non-zero period length.
+		InvalidMintLimit,
+		/// This source chain transaction was already minted.
+		BridgeTxUsed,
+		/// The mint would take the bridge above its mint cap.
+		BridgeCapExceeded,
+		SwapNotFound,
+		/// The preimage does not hash to the swap's hashlock.
+		InvalidPreimage,
+		/// The swap timelock must lie in the future.
+		InvalidTimelock,
+		/// The swap timelock passed, it can only be refunded.
+		SwapExpired,
+		/// The swap timelock has not passed yet, it can only be claimed.
+		SwapNotExpired,
+		StreamNotFound,
+		/// Streams need a deposit and a `start` before `stop`, not in the past.
+		InvalidStream,
+		/// Only the stream's sender or recipient may do this.
+		NotStreamParty,
+		/// More than has streamed to the recipient so far.
+		InsufficientStreamBalance,
+		EscrowNotFound,
+		/// The caller may not settle or dispute this escrow.
+		NotEscrowParty,
+		/// The escrow is already disputed.
+		EscrowDisputed,
+		/// Nobody but the contract holds tokens to distribute to.
+		NoDividendHolders,
+		NoDividend,
+		/// The contract was not deployed with `new_rebasing`.
+		NotRebasing,
+		/// The rebase would take supply to zero, or beyond what the gons can represent.
+		InvalidRebase,
+		/// No bonding curve is set.
+		NoCurve,
+		/// The curve cannot be set up in wrapped mode, or changed while it has tokens out.
+		CurveUnavailable,
+		/// More than the curve has minted is being sold back.
+		InsufficientCurveSupply,
+		/// `MAX_EXCLUDED_ACCOUNTS` are already excluded from circulation.
+		TooManyExcludedAccounts,
+		/// The allowance passed its expiry.
+		AllowanceExpired,
+		/// The expiry of an allowance must lie in the future.
+		InvalidExpiry,
+		/// A recurring allowance needs a non-zero period.
+		InvalidRecurringAllowance,
+		/// Rescuing would dip into native currency that backs the wrapped supply, sale proceeds or curve reserve.
+		RescueExceedsSurplus,
+		/// This token's own balance held by the contract backs escrows, streams and stakes.
+		CannotRescueOwnToken,
+		/// The foreign token's `transfer` failed.
+		RescueFailed,
+		/// Built without the `mutable-metadata` feature, so name and symbol are fixed.
+		MetadataImmutable,
+		/// Empty, longer than `MAX_NAME_LEN`, padded with whitespace or containing control characters.
+		InvalidName,
+		/// Empty, longer than `MAX_SYMBOL_LEN`, or not plain alphanumeric.
+		InvalidSymbol,
+		/// `buy` is unavailable in wrapped mode, where its proceeds would draw on the native backing.
+		SaleUnavailable,
+		/// The extension behind this message was left out of the build by its cargo feature.
+		ExtensionDisabled,
+		/// The timelock delay can only be raised.
+		TimelockDelayDecrease,
+		/// The caller has no time lock at this index.
+		LockNotFound,
+		/// The contract holds tokens for someone, or dividends were distributed, which a rebase would misprice.
+		RebaseWithCustody,
+		/// A bonding curve needs non-zero virtual reserves on both sides.
+		InvalidCurve,
+		/// The holders given for a balance root are not exactly the tracked holders.
+		HoldersIncomplete,
+		/// The zero account was given where a real one is needed, such as a spender.
+		ZeroAddress,
+		/// The accounts and amounts of a batch differ in length.
+		BatchLengthMismatch,
+	}
+
+	/// The result type returned by the contract's messages.
+	pub type Result<T> = core::result::Result<T, Error>;
+
+	impl From<Error> for PSP22Error {
+		fn from(error: Error) -> Self {
+			match error {
+				Error::InsufficientBalance => PSP22Error::InsufficientBalance,
+				Error::InsufficientAllowance => PSP22Error::InsufficientAllowance,
+				Error::SafeTransferCheckFailed(reason) => PSP22Error::SafeTransferCheckFailed(reason),
+				error => PSP22Error::Custom(ink_prelude::format!("{:?}", error)),
+			}
+		}
+	}
+
+	/// Layout version of the events this code emits; bump it whenever an event is added or changed.
+	///
+	/// 1: `Transferred`, `DelayedTransferQueued`, `BridgeLocked`, `BridgeUnlocked`, `MetadataUpdated`.
+	/// 2: adds `Approval`.
+	/// 3: adds `OwnershipTransferStarted`, `OwnershipTransferred`.
+	/// 4: adds `Paused`, `Unpaused`.
+	/// 5: adds `RoleGranted`, `RoleRevoked`.
+	/// 6: `Transferred` no longer carries mints and burns, which move to `Minted`, `Burned`;
+	///    account fields of `Transferred` and `Approval` become topics.
+	/// 7: adds `Snapshot`.
+	/// 8: adds `DelegateChanged`, `DelegateVotesChanged`.
+	/// 9: adds `VestingScheduleCreated`, `VestingReleased`, `VestingRevoked`.
+	/// 10: adds `TokensLocked`, `UnlockedTokensClaimed`.
+	/// 11: adds `FeeCollected`.
+	/// 12: adds `Staked`, `Unstaked`, `RewardPaid`, `RewardsFunded`.
+	/// 13: adds `AirdropClaimed`.
+	/// 14: adds `TokensPurchased`.
+	/// 15: adds `FlashLoan`.
+	/// 16: adds `AccountFrozen`, `AccountUnfrozen`.
+	/// 17: adds `TradingEnabled`.
+	/// 18: adds `AdminActionProposed`, `AdminActionApproved`, `AdminActionExecuted`.
+	/// 19: adds `OperationQueued`, `OperationExecuted`, `OperationCancelled`.
+	/// 20: adds `Upgraded`; `OperationQueued` and `AdminActionProposed` can carry `AdminAction::Upgrade`.
+	/// 21: adds `BridgeMinted`, `BridgeBurned`.
+	/// 22: adds `SwapCreated`, `SwapClaimed`, `SwapRefunded`.
+	/// 23: adds `StreamCreated`, `WithdrawFromStream`, `StreamCancelled`.
+	/// 24: adds `EscrowCreated`, `EscrowReleased`, `EscrowRefunded`, `EscrowDisputed`.
+	/// 25: adds `DividendsDistributed`, `DividendWithdrawn`.
+	/// 26: adds `Rebase`.
+	/// 27: adds `CurveBought`, `CurveSold`.
+	/// 28: `MetadataUpdated` carries the name and symbol alongside the metadata URI.
+	/// 29: adds `TokenUriUpdated`, emitted with `MetadataUpdated` whenever the metadata URI changes.
+	/// 30: adds `OperatorSet`.
+	/// 31: adds `RecurringApproval`.
+	/// 32: adds `Rescued`.
+	/// 33: adds `LockRejected`.
+	pub const EVENT_SCHEMA_VERSION: u16 = 33;
+
+	/// Identifies a role granted through `grant_role`.
+	pub type RoleId = u32;
+
+	/// May call `mint`.
+	pub const MINTER_ROLE: RoleId = ink_lang::selector_id!("MINTER_ROLE");
+	/// May call `burn` and `burn_with_option`.
+	pub const BURNER_ROLE: RoleId = ink_lang::selector_id!("BURNER_ROLE");
+	/// May call `pause` and `unpause`.
+	pub const PAUSER_ROLE: RoleId = ink_lang::selector_id!("PAUSER_ROLE");
+	/// May call `snapshot`.
+	pub const SNAPSHOT_ROLE: RoleId = ink_lang::selector_id!("SNAPSHOT_ROLE");
+	/// May call `freeze_account` and `unfreeze_account`.
+	pub const FREEZER_ROLE: RoleId = ink_lang::selector_id!("FREEZER_ROLE");
+	/// May call `bridge_mint` and `bridge_burn`.
+	pub const BRIDGE_ROLE: RoleId = ink_lang::selector_id!("BRIDGE_ROLE");
+	/// May call `rebase`.
+	pub const REBASER_ROLE: RoleId = ink_lang::selector_id!("REBASER_ROLE");
+
+	/// Every role, all held by the owner from the start.
+	const OWNER_ROLES: [RoleId; 7] = [MINTER_ROLE, BURNER_ROLE, PAUSER_ROLE, SNAPSHOT_ROLE, FREEZER_ROLE, BRIDGE_ROLE, REBASER_ROLE];
+
+	/// Gons each token starts out as in a rebasing contract; the headroom for positive rebases.
+	pub const INITIAL_GONS_PER_FRAGMENT: Balance = 1_000_000;
+
+	/// Identifies a snapshot taken through `snapshot`, starting at 1.
+	pub type SnapshotId = u32;
+
+	/// Storage layout version of this code; bump it together with `migrate` when adding fields.
+	/// 2: moves every field added since 1 into `StorageV2`, after the fields of 1.
+	pub const STORAGE_VERSION: u32 = 2;
+
+	/// Default time after which an unresolved conditional transfer can be reclaimed (7 days).
+	pub const DEFAULT_CONDITIONAL_TIMEOUT: Timestamp = 7 * 24 * 60 * 60 * 1000;
+
+	/// Largest number of decimals whose unit, `10^decimals`, still fits in a `Balance`.
+	pub const MAX_DECIMALS: u8 = 38;
+
+	/// Upper bound on the length in bytes of the metadata URI.
+	pub const MAX_METADATA_URI_LEN: usize = 256;
+
+	/// Upper bound on the length in bytes of a name passed to `set_name`.
+	pub const MAX_NAME_LEN: usize = 64;
+
+	/// Upper bound on the length in bytes of a symbol passed to `set_symbol`.
+	pub const MAX_SYMBOL_LEN: usize = 12;
+
+	/// Upper bound on the number of reads a single `multicall_read` may batch.
+	pub const MAX_READ_CALLS: usize = 32;
+
+	/// Upper bound on the number of messages a single `multicall` may batch.
+	pub const MAX_MULTICALL_CALLS: usize = 16;
+
+	/// `detect_transfer_restriction` code for an unrestricted transfer.
+	pub const RESTRICTION_SUCCESS: u8 = 0;
+	/// Token movement is paused.
+	pub const RESTRICTION_PAUSED: u8 = 1;
+	/// The sender or recipient is frozen.
+	pub const RESTRICTION_FROZEN: u8 = 2;
+	/// The external rules contract could not be called.
+	pub const RESTRICTION_RULES_UNAVAILABLE: u8 = 3;
+	/// Allowlist mode is on and the sender or recipient is not allowlisted.
+	pub const RESTRICTION_NOT_ALLOWLISTED: u8 = 4;
+
+	/// Basis points in a whole, i.e. 100%.
+	pub const BPS_DENOMINATOR: Balance = 10_000;
+
+	/// Highest transfer fee the owner can set, 10%.
+	pub const MAX_FEE_BPS: u16 = 1_000;
+
+	/// Highest share of each transfer the owner can have burned, 10%.
+	pub const MAX_BURN_RATE_BPS: u16 = 1_000;
+
+	/// Fixed-point scale of the staking reward-per-token and dividend-per-share accumulators.
+	pub const REWARD_PRECISION: Balance = 1_000_000_000_000;
+
+	/// Upper bound on pending time locks per recipient, so nobody can bloat another account's `claim_unlocked`.
+	/// Recipients free slots filled with unwanted locks through `reject_lock`.
+	pub const MAX_LOCKS_PER_ACCOUNT: usize = 32;
+
+	/// Most accounts `circulating_supply` leaves out, so the query stays cheap.
+	pub const MAX_EXCLUDED_ACCOUNTS: usize = 32;
+
+	/// Tags a signed `permit` payload so it cannot be replayed as another kind of signed message.
+	pub const PERMIT_TYPE: [u8; 4] = ink_lang::selector_bytes!("permit");
+
+	/// Tags a signed `transfer_with_authorization` payload.
+	pub const TRANSFER_WITH_AUTHORIZATION_TYPE: [u8; 4] = ink_lang::selector_bytes!("transfer_with_authorization");
+
+	/// A read query that can be batched through `multicall_read`.
+	#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub enum ReadCall {
+		BalanceOf(AccountId),
+		/// `(owner, spender)`
+		Allowance(AccountId, AccountId),
+		TotalSupply,
+	}
+
+	/// A message `multicall` can run, decoded from its selector and SCALE-encoded arguments.
+	enum BatchedCall {
+		Transfer(AccountId, Balance),
+		Approve(AccountId, Balance),
+		IncreaseAllowance(AccountId, Balance),
+		DecreaseAllowance(AccountId, Balance),
+		TransferFrom(AccountId, AccountId, Balance),
+		Stake(Balance),
+		Unstake(Balance),
+	}
+
+	impl BatchedCall {
+		const TRANSFER: [u8; 4] = ink_lang::selector_bytes!("transfer");
+		const APPROVE: [u8; 4] = ink_lang::selector_bytes!("approve");
+		const INCREASE_ALLOWANCE: [u8; 4] = ink_lang::selector_bytes!("increase_allowance");
+		const DECREASE_ALLOWANCE: [u8; 4] = ink_lang::selector_bytes!("decrease_allowance");
+		const TRANSFER_FROM: [u8; 4] = ink_lang::selector_bytes!("transfer_from");
+		const STAKE: [u8; 4] = ink_lang::selector_bytes!("stake");
+		const UNSTAKE: [u8; 4] = ink_lang::selector_bytes!("unstake");
+
+		fn decode(payload: &[u8]) -> Result<Self> {
+			if payload.len() < 4 {
+				return Err(Error::InvalidCallData);
+			}
+			let (selector, mut args) = payload.split_at(4);
+			match [selector[0], selector[1], selector[2], selector[3]] {
+				Self::TRANSFER => Self::decode_args(&mut args).map(|(to, amount)| Self::Transfer(to, amount)),
+				Self::APPROVE => Self::decode_args(&mut args).map(|(spender, amount)| Self::Approve(spender, amount)),
+				Self::INCREASE_ALLOWANCE => Self::decode_args(&mut args).map(|(spender, delta)| Self::IncreaseAllowance(spender, delta)),
+				Self::DECREASE_ALLOWANCE => Self::decode_args(&mut args).map(|(spender, delta)| Self::DecreaseAllowance(spender, delta)),
+				Self::TRANSFER_FROM => Self::decode_args(&mut args).map(|(from, to, amount)| Self::TransferFrom(from, to, amount)),
+				Self::STAKE => Self::decode_args(&mut args).map(Self::Stake),
+				Self::UNSTAKE => Self::decode_args(&mut args).map(Self::Unstake),
+				_ => Err(Error::UnsupportedCall),
+			}
+		}
+
+		fn decode_args<T: scale::Decode>(args: &mut &[u8]) -> Result<T> {
+			<T as scale::DecodeAll>::decode_all(args).map_err(|_| Error::InvalidCallData)
+		}
+	}
+
+	/// Tokens escrowed by `maker` in exchange for `want_amount` of `want_token` from `counterparty`.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub struct SwapOffer {
+		pub maker: AccountId,
+		pub counterparty: AccountId,
+		pub give_amount: Balance,
+		pub want_token: AccountId,
+		pub want_amount: Balance,
+	}
+
+	/// A privileged call the admin multisig can propose, approve and execute.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub enum AdminAction {
+		Mint { to: AccountId, amount: Balance },
+		Pause,
+		Unpause,
+		SetFee { fee_bps: u16, collector: Option<AccountId> },
+		TransferOwnership { new_owner: AccountId },
+		Upgrade { code_hash: Hash },
+	}
+
+	/// An `AdminAction` waiting out the timelock delay.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub struct QueuedOperation {
+		pub operation: AdminAction,
+		pub executable_at: BlockNumber,
+	}
+
+	/// A staking reward epoch once it is over.
+	#[derive(Debug, Clone, Default, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub struct RewardEpoch {
+		/// Block the epoch's rewards stopped at.
+		pub end: BlockNumber,
+		/// The epoch's rewards per token staked for one block of it, scaled by `REWARD_PRECISION`.
+		pub reward_per_stake_block: Balance,
+		/// Rewards per token staked through this and every earlier epoch, scaled by `REWARD_PRECISION`.
+		pub reward_per_token: Balance,
+	}
+
+	/// A large transfer held in escrow until `executable_at`.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub struct DelayedTransfer {
+		pub from: AccountId,
+		pub to: AccountId,
+		pub amount: Balance,
+		pub executable_at: Timestamp,
+	}
+
+	/// Tokens escrowed until `resolver` reports whether `condition_id` is met.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub struct ConditionalTransfer {
+		pub from: AccountId,
+		pub to: AccountId,
+		pub amount: Balance,
+		pub resolver: AccountId,
+		pub expires_at: Timestamp,
+	}
+
+	/// Tokens escrowed for `recipient` until whoever knows the Sha2x256 preimage of `hashlock` claims
+	/// them, or refundable to `sender` from `timelock` on.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub struct HashLockedSwap {
+		pub sender: AccountId,
+		pub recipient: AccountId,
+		pub amount: Balance,
+		pub hashlock: Hash,
+		pub timelock: Timestamp,
+	}
+
+	/// Tokens held in the contract and released linearly to `beneficiary` from `start + cliff` until `start + duration`.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub struct VestingSchedule {
+		pub start: Timestamp,
+		pub cliff: Timestamp,
+		pub duration: Timestamp,
+		pub amount: Balance,
+		pub released: Balance,
+	}
+
+	impl VestingSchedule {
+		/// How much of `amount` has vested by `now`.
+		pub fn vested_at(&self, now: Timestamp) -> Balance {
+			let elapsed = now.saturating_sub(self.start);
+			if elapsed < self.cliff {
+				return 0;
+			}
+			if elapsed >= self.duration {
+				return self.amount;
+			}
+			// Split the division so `amount * elapsed` cannot overflow.
+			let (duration, elapsed) = (Balance::from(self.duration), Balance::from(elapsed));
+			self.amount / duration * elapsed + self.amount % duration * elapsed / duration
+		}
+	}
+
+	/// Constant-product bonding curve between native currency and tokens, starting from virtual reserves:
+	/// `(virtual_reserve + reserve) * (virtual_tokens - minted)` stays constant as tokens are bought and sold.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub struct BondingCurve {
+		pub virtual_reserve: Balance,
+		pub virtual_tokens: Balance,
+		/// Native currency paid in and not yet paid back out.
+		pub reserve: Balance,
+		/// Tokens minted by the curve and not yet sold back.
+		pub minted: Balance,
+	}
+
+	impl BondingCurve {
+		fn pool_reserve(&self) -> Option<Balance> {
+			self.virtual_reserve.checked_add(self.reserve)
+		}
+
+		fn pool_tokens(&self) -> Balance {
+			self.virtual_tokens - self.minted
+		}
+
+		/// Tokens `paid` buys, rounded down.
+		pub fn tokens_for(&self, paid: Balance) -> Option<Balance> {
+			let pool_reserve = self.pool_reserve()?.checked_add(paid)?;
+			self.pool_tokens().checked_mul(paid).map(|product| product / pool_reserve)
+		}
+
+		/// Native currency selling `amount` pays, rounded down.
+		pub fn payout_for(&self, amount: Balance) -> Option<Balance> {
+			let pool_tokens = self.pool_tokens().checked_add(amount)?;
+			self.pool_reserve()?.checked_mul(amount).map(|product| product / pool_tokens)
+		}
+
+		/// Native currency needed to buy `amount`, rounded up; `None` if the curve cannot supply it.
+		pub fn cost_of(&self, amount: Balance) -> Option<Balance> {
+			let pool_tokens = self.pool_tokens().checked_sub(amount).filter(|left| *left > 0)?;
+			let product = self.pool_reserve()?.checked_mul(amount)?;
+			Some(product / pool_tokens + Balance::from(product % pool_tokens != 0))
+		}
+	}
+
+	/// `amount` of the buyer's tokens held in the contract until released to `seller` or refunded.
+	/// Once `disputed`, only `arbiter` can settle it.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub struct Escrow {
+		pub buyer: AccountId,
+		pub seller: AccountId,
+		pub arbiter: AccountId,
+		pub amount: Balance,
+		pub disputed: bool,
+	}
+
+	/// `deposit` held in the contract and streamed to `recipient` linearly from `start` to `stop`.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub struct Stream {
+		pub sender: AccountId,
+		pub recipient: AccountId,
+		pub deposit: Balance,
+		pub start: Timestamp,
+		pub stop: Timestamp,
+		pub withdrawn: Balance,
+	}
+
+	impl Stream {
+		/// How much of `deposit` has streamed by `now`.
+		pub fn streamed_at(&self, now: Timestamp) -> Balance {
+			if now <= self.start {
+				return 0;
+			}
+			if now >= self.stop {
+				return self.deposit;
+			}
+			// Split the division so `deposit * elapsed` cannot overflow.
+			let (duration, elapsed) = (Balance::from(self.stop - self.start), Balance::from(now - self.start));
+			self.deposit / duration * elapsed + self.deposit % duration * elapsed / duration
+		}
+	}
+
+	/// Tokens held in the contract until `unlock_at`.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub struct TimeLock {
+		/// Who locked the tokens, and gets them back if the recipient rejects the lock.
+		pub from: AccountId,
+		pub amount: Balance,
+		pub unlock_at: Timestamp,
+	}
+
+	/// Everything a wallet needs to display the token, see `token_info`.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct TokenInfo {
+		pub name: String,
+		pub symbol: String,
+		pub decimals: u8,
+		pub metadata_uri: String,
+		pub total_supply: Balance,
+		pub cap: Option<Balance>,
+		pub owner: AccountId,
+		pub paused: bool,
+		pub fee_bps: u16,
+		pub fee_collector: Option<AccountId>,
+		pub burn_rate_bps: u16,
+	}
+
+	/// Lets a spender pull up to `amount_per_period` every `period_blocks`, see `approve_recurring`.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo, StorageLayout))]
+	pub struct RecurringAllowance {
+		pub amount_per_period: Balance,
+		pub period_blocks: BlockNumber,
+		/// First block of the period `spent` counts against.
+		pub period_start: BlockNumber,
+		pub spent: Balance,
+	}
+
+	impl RecurringAllowance {
+		/// Rolls over to the period containing `now`, resetting `spent` if a new one started.
+		pub fn reset_at(&mut self, now: BlockNumber) {
+			let elapsed_periods = now.saturating_sub(self.period_start) / self.period_blocks;
+			if elapsed_periods > 0 {
+				self.period_start += elapsed_periods * self.period_blocks;
+				self.spent = 0;
+			}
+		}
+	}
+
+	/// Every configurable limit, `None` where the limit is disabled.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct ActiveLimits {
+		pub max_mint_per_tx: Option<Balance>,
+		pub max_tx_amount: Option<Balance>,
+		pub max_wallet_balance: Option<Balance>,
+		pub cooldown_blocks: Option<BlockNumber>,
+		pub mint_limit_per_period: Option<Balance>,
+		pub mint_period_length: Option<BlockNumber>,
+		pub large_transfer_threshold: Option<Balance>,
+		pub large_transfer_delay: Option<Timestamp>,
+	}
+
+	/// The real sender a trusted forwarder appends as the last 32 bytes of the call data.
+	pub struct ForwardedSender(AccountId);
+
+	impl scale::Decode for ForwardedSender {
+		fn decode<I: scale::Input>(input: &mut I) -> core::result::Result<Self, scale::Error> {
+			let len = input.remaining_len()?.ok_or("ERC20: unknown call data length")?;
+			// The selector always precedes the appended sender.
+			if len < 4 + 32 {
+				return Err("ERC20: no forwarded sender in call data".into());
+			}
+
+			let mut call = vec![0u8; len - 32];
+			input.read(&mut call)?;
+			let mut sender = [0u8; 32];
+			input.read(&mut sender)?;
+
+			Ok(Self(AccountId::from(sender)))
+		}
+	}
+
+	/// Storage fields added since layout version 1, in the order they were added.
+	#[derive(SpreadLayout, SpreadAllocate)]
+	#[cfg_attr(feature = "std", derive(StorageLayout))]
+	#[cfg_attr(test, derive(Debug))]
+	struct StorageV2 {
+		/// Proposed by `transfer_ownership`, becomes owner on `accept_ownership`.
+		_pending_owner: Option<AccountId>,
+		_paused: bool,
+		/// Roles are granted and revoked by the owner.
+		_roles: Mapping<(RoleId, AccountId), bool>,
+		/// `(owner, spender)` to the amount `spender` may still move out of `owner`'s balance.
+		_allowances: Mapping<(AccountId, AccountId), Balance>,
+		_decimals: u8,
+		/// Hard ceiling on total supply, fixed at construction.
+		_cap: Option<Balance>,
+		/// Rewards pool that redirected burns are sent to.
+		_burn_redirect: Option<AccountId>,
+		_balance_root: Hash,
+		_balance_root_at: Timestamp,
+		_bridge_relayer: Option<AccountId>,
+		/// `(src_chain, nonce)` pairs already unlocked.
+		_bridge_nonces: Mapping<(u32, u64), bool>,
+		/// Keyed by sender, so nobody can take another sender's condition id.
+		_conditional_transfers: Mapping<(AccountId, Hash), ConditionalTransfer>,
+		_conditional_timeout: Timestamp,
+		/// Next nonce each account must sign into a `permit`.
+		_nonces: Mapping<AccountId, u64>,
+		/// Authorization nonces already used by each authorizer.
+		_authorization_states: Mapping<(AccountId, Hash), bool>,
+		_current_snapshot_id: SnapshotId,
+		/// Per-account `(snapshot id, balance)` checkpoints by index, ascending, written on the first balance
+		/// change after a snapshot.
+		_account_snapshots: Mapping<(AccountId, u32), (SnapshotId, Balance)>,
+		_account_snapshot_counts: Mapping<AccountId, u32>,
+		/// Total supply checkpoints, kept like `_account_snapshots`.
+		_total_supply_snapshots: Mapping<u32, (SnapshotId, Balance)>,
+		_total_supply_snapshot_count: u32,
+		_delegates: Mapping<AccountId, AccountId>,
+		/// Per-delegate `(block number, votes)` checkpoints by index, ascending, at most one per block. Kept
+		/// one entry per cell so nobody can grow a delegate's history past what a single cell can hold.
+		_vote_checkpoints: Mapping<(AccountId, u32), (BlockNumber, Balance)>,
+		_vote_checkpoint_counts: Mapping<AccountId, u32>,
+		_vesting_schedules: Mapping<AccountId, VestingSchedule>,
+		/// Pending time locks per recipient.
+		_locks: Mapping<AccountId, Vec<TimeLock>>,
+		/// Share of each transfer, in basis points, paid to `_fee_collector`; no fee while the collector is unset.
+		_fee_bps: u16,
+		_fee_collector: Option<AccountId>,
+		_fee_exempt: Mapping<AccountId, bool>,
+		/// Share of each transfer, in basis points, burned after the fee; fee-exempt accounts are exempt here too.
+		_burn_rate_bps: u16,
+		/// Everything ever burned, by `burn`, `burn_from`, bridging or the transfer burn.
+		_total_burned: Balance,
+		/// Tokens each account has staked; they are held in the contract's own account.
+		_staked: Mapping<AccountId, Balance>,
+		_total_staked: Balance,
+		/// Rewards released per block of the current epoch until `_reward_period_end`, then shared by each
+		/// staker's time-weighted stake over the epoch.
+		_reward_rate: Balance,
+		_reward_period_end: BlockNumber,
+		/// Epoch `fund_rewards` last started, 0 before the first.
+		_reward_epoch: u32,
+		_reward_epoch_start: BlockNumber,
+		/// Total stake summed over each block of the current epoch up to `_reward_updated_at`.
+		_total_stake_blocks: Balance,
+		_reward_updated_at: BlockNumber,
+		/// Every epoch before the current one.
+		_reward_epochs: Mapping<u32, RewardEpoch>,
+		/// Per account `(epoch, stake summed over each block of it, block summed up to)`.
+		_stake_blocks: Mapping<AccountId, (u32, Balance, BlockNumber)>,
+		/// Settled but unclaimed rewards.
+		_rewards: Mapping<AccountId, Balance>,
+		_merkle_root: Option<Hash>,
+		/// Claimed airdrop indices, 128 per word, kept per root so a new airdrop starts unclaimed.
+		_claimed_bitmap: Mapping<(Hash, u64), u128>,
+		/// Tokens minted per unit of native currency paid into `buy`, 0 while the sale is closed.
+		_sale_rate: Balance,
+		/// Native currency received by `buy` and not yet withdrawn.
+		_sale_proceeds: Balance,
+		/// Set by `new_wrapped`: supply is backed 1:1 by native currency held in the contract.
+		_wrapped: bool,
+		/// Fee on flash loans in basis points, burned together with the principal.
+		_flash_fee_bps: u16,
+		/// Set while a guarded message runs. Kept in a `Mapping` because those write through to storage at once,
+		/// so a re-entrant call sees it; plain fields are only written back when the outer call returns.
+		_reentrancy_lock: Mapping<(), bool>,
+		/// Accounts that can neither send nor receive tokens.
+		_frozen: Mapping<AccountId, bool>,
+		/// Contract consulted through `detect_transfer_restriction` on every transfer, if set.
+		_restriction_rules: Option<AccountId>,
+		_allowlist_enabled: bool,
+		_allowlist: Mapping<AccountId, bool>,
+		/// Largest single transfer, 0 for no limit.
+		_max_tx_amount: Balance,
+		/// Largest balance a transfer may leave the recipient with, 0 for no limit.
+		_max_wallet_balance: Balance,
+		_limit_exempt: Mapping<AccountId, bool>,
+		_trading_enabled: bool,
+		/// Blocks a non-exempt account must wait between transfers, 0 for no cooldown.
+		_cooldown_blocks: BlockNumber,
+		_last_transfer_block: Mapping<AccountId, BlockNumber>,
+		_admin_signers: Vec<AccountId>,
+		_admin_threshold: u32,
+		_admin_actions: Mapping<u64, AdminAction>,
+		_admin_approvals: Mapping<(u64, AccountId), bool>,
+		_next_admin_action_id: u64,
+		/// Blocks a queued operation waits before it can run, 0 when privileged calls are direct.
+		_timelock_delay: BlockNumber,
+		_queued_operations: Mapping<u64, QueuedOperation>,
+		_next_operation_id: u64,
+		/// Most that may be minted per period of `_mint_period_length` blocks, 0 for no limit.
+		_mint_limit_per_period: Balance,
+		_mint_period_length: BlockNumber,
+		/// Period `_minted_in_period` was counted in, as block number divided by period length.
+		_mint_period: BlockNumber,
+		_minted_in_period: Balance,
+		/// `(src_chain, src_tx)` pairs already minted through `bridge_mint`.
+		_bridge_src_txs: Mapping<(u32, Hash), bool>,
+		/// Most a bridge may have outstanding, i.e. minted and not burned back; uncapped if unset.
+		_bridge_mint_caps: Mapping<AccountId, Balance>,
+		_bridge_minted: Mapping<AccountId, Balance>,
+		_hashlocked_swaps: Mapping<u64, HashLockedSwap>,
+		_next_hashlocked_swap_id: u64,
+		_streams: Mapping<u64, Stream>,
+		_next_stream_id: u64,
+		_escrows: Mapping<u64, Escrow>,
+		_next_escrow_id: u64,
+		/// Dividends per held token since the start, scaled by `REWARD_PRECISION`.
+		_magnified_dividend_per_share: Balance,
+		/// Offsets `_magnified_dividend_per_share * balance` for balance changes, so past dividends stay put.
+		_magnified_dividend_corrections: Mapping<AccountId, i128>,
+		_withdrawn_dividends: Mapping<AccountId, Balance>,
+		/// Set in rebasing mode, where `_balances` holds gons and a balance is its gons divided by this.
+		_gons_per_fragment: Option<Balance>,
+		_total_gons: Balance,
+		_rebase_epoch: u64,
+		_curve: Option<BondingCurve>,
+		/// Accounts with a non-zero balance, densely indexed from 0 to `_holder_count`.
+		_holders: Mapping<u32, AccountId>,
+		/// Position of each holder in `_holders`.
+		_holder_index: Mapping<AccountId, u32>,
+		_holder_count: u32,
+		/// Accounts whose balances `circulating_supply` leaves out.
+		_excluded_accounts: Vec<AccountId>,
+		/// `(owner, operator)` pairs where the operator may `transfer_from` any amount.
+		_operators: Mapping<(AccountId, AccountId), bool>,
+		/// When allowances given through `approve_with_expiry` stop counting.
+		_allowance_expiries: Mapping<(AccountId, AccountId), Timestamp>,
+		/// Spenders each owner has a non-zero allowance for, expired or not, densely indexed from 0 to the
+		/// owner's `_spender_counts`.
+		_spenders: Mapping<(AccountId, u32), AccountId>,
+		/// Position of each `(owner, spender)` pair in `_spenders`.
+		_spender_index: Mapping<(AccountId, AccountId), u32>,
+		_spender_counts: Mapping<AccountId, u32>,
+		_recurring_allowances: Mapping<(AccountId, AccountId), RecurringAllowance>,
+	}
+
+	/// Fields appended to a released layout. Code upgraded in place finds none of their cells written, so
+	/// they read as defaults until `migrate` runs; pulled as plain fields, they would trap on the missing cells.
+	#[cfg_attr(test, derive(Debug))]
+	struct Migratable<T>(T);
+
+	impl<T: SpreadAllocate> SpreadLayout for Migratable<T> {
+		const FOOTPRINT: u64 = 1 + <T as SpreadLayout>::FOOTPRINT;
+
+		fn pull_spread(ptr: &mut KeyPtr) -> Self {
+			let written = ink_env::get_contract_storage::<bool>(ptr.next_for::<bool>()).ok().flatten().unwrap_or(false);
+			if !written {
+				// Allocation places fields after an `Option` one cell off from where they are pulled from,
+				// so store the defaults and pull them back rather than allocating.
+				let mut allocate_ptr = *ptr;
+				let mut push_ptr = *ptr;
+				T::allocate_spread(&mut allocate_ptr).push_spread(&mut push_ptr);
+			}
+
+			Self(T::pull_spread(ptr))
+		}
+
+		fn push_spread(&self, ptr: &mut KeyPtr) {
+			SpreadLayout::push_spread(&true, ptr);
+			self.0.push_spread(ptr);
+		}
+
+		fn clear_spread(&self, ptr: &mut KeyPtr) {
+			SpreadLayout::clear_spread(&true, ptr);
+			self.0.clear_spread(ptr);
+		}
+	}
+
+	impl<T: SpreadAllocate> SpreadAllocate for Migratable<T> {
+		fn allocate_spread(ptr: &mut KeyPtr) -> Self {
+			ptr.next_for::<bool>();
+			Self(T::allocate_spread(ptr))
+		}
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: StorageLayout> StorageLayout for Migratable<T> {
+		fn layout(key_ptr: &mut KeyPtr) -> Layout {
+			Layout::Struct(StructLayout::new([
+				FieldLayout::new("written", <bool as StorageLayout>::layout(key_ptr)),
+				FieldLayout::new("fields", <T as StorageLayout>::layout(key_ptr)),
+			]))
+		}
+	}
+
+	impl<T> core::ops::Deref for Migratable<T> {
+		type Target = T;
+
+		fn deref(&self) -> &T {
+			&self.0
+		}
+	}
+
+	impl<T> core::ops::DerefMut for Migratable<T> {
+		fn deref_mut(&mut self) -> &mut T {
+			&mut self.0
+		}
+	}
+
+	#[ink(storage)]
+	#[derive(SpreadAllocate)]
+    pub struct Erc20 {
+		_owner: AccountId,
+        _balances: Mapping<AccountId, Balance>,
+		_total_supply: Balance,
+		_name: String,
+		_symbol: String,
+		_twab_start: Timestamp,
+		/// Per-account `(cumulative balance * elapsed, last update timestamp)`.
+		_twab_accumulators: Mapping<AccountId, (Balance, Timestamp)>,
+		_trusted_forwarder: Option<AccountId>,
+		_metadata_uri: String,
+		/// Largest amount a single mint may create, 0 for no limit.
+		_max_mint_per_tx: Balance,
+		_swap_offers: Mapping<u64, SwapOffer>,
+		_next_swap_offer_id: u64,
+		/// Transfers above this amount are queued, 0 disables the delay.
+		_large_transfer_threshold: Balance,
+		_large_transfer_delay: Timestamp,
+		_delayed_transfers: Mapping<u64, DelayedTransfer>,
+		_next_delayed_transfer_id: u64,
+		_version: u32,
+		/// Everything added since layout version 1.
+		_v2: Migratable<StorageV2>,
+    }
+
+    impl Erc20 {
+        /// `total_supply` is in base units, i.e. `1` is `10^-decimals` of a whole token.
+        #[ink(constructor)]
+        pub fn new(total_supply: Balance, name: String, symbol: String, decimals: u8) -> Self {
+			Self::_init(Self::env().caller(), total_supply, name, symbol, decimals, None)
+        }
+
+        /// Like `new`, but supply and ownership go to `owner` rather than the deployer, e.g. a factory.
+        #[ink(constructor)]
+        pub fn new_for(owner: AccountId, total_supply: Balance, name: String, symbol: String, decimals: u8) -> Self {
+			Self::_init(owner, total_supply, name, symbol, decimals, None)
+        }
+
+        /// Like `new`, but total supply can never be minted past `cap`.
+        #[ink(constructor)]
+        pub fn new_capped(total_supply: Balance, name: String, symbol: String, decimals: u8, cap: Balance) -> Self {
+			Self::_init(Self::env().caller(), total_supply, name, symbol, decimals, Some(cap))
+        }
+
+        /// A wrapper around the native currency: supply starts at zero and only grows through `deposit`.
+        #[ink(constructor)]
+        pub fn new_wrapped(name: String, symbol: String, decimals: u8) -> Self {
+			let mut contract = Self::_init(Self::env().caller(), 0, name, symbol, decimals, None);
+			contract._v2._wrapped = true;
+			contract
+        }
+
+        /// Like `new`, but `rebase` can later scale every balance by the same factor.
+        #[ink(constructor)]
+        pub fn new_rebasing(total_supply: Balance, name: String, symbol: String, decimals: u8) -> Self {
+			let total_gons = total_supply.checked_mul(INITIAL_GONS_PER_FRAGMENT).expect("ERC20: supply too large to rebase");
+			let mut contract = Self::_init(Self::env().caller(), total_supply, name, symbol, decimals, None);
+			contract._v2._gons_per_fragment = Some(INITIAL_GONS_PER_FRAGMENT);
+			contract._v2._total_gons = total_gons;
+			contract._balances.insert(Self::env().caller(), &total_gons);
+			contract
+        }
+
+        /// Like `new`, but only limit-exempt accounts can transfer until the owner calls `enable_trading`.
+        #[ink(constructor)]
+        pub fn new_prelaunch(total_supply: Balance, name: String, symbol: String, decimals: u8) -> Self {
+			let mut contract = Self::_init(Self::env().caller(), total_supply, name, symbol, decimals, None);
+			contract._v2._trading_enabled = false;
+			contract
+        }
+
+		fn _init(owner: AccountId, total_supply: Balance, name: String, symbol: String, decimals: u8, cap: Option<Balance>) -> Self {
+			assert!(decimals <= MAX_DECIMALS, "ERC20: too many decimals");
+			assert!(cap.is_none_or(|cap| total_supply <= cap), "ERC20: initial supply exceeds cap");
+
+			ink_lang::utils::initialize_contract(|contract: &mut Self| {
+				contract._version = STORAGE_VERSION;
+				contract._v2._conditional_timeout = DEFAULT_CONDITIONAL_TIMEOUT;
+				contract._twab_start = Self::env().block_timestamp();
+				contract._update_twab(&owner);
+                contract._balances.insert(owner, &total_supply);
+				if total_supply > 0 {
+					contract._add_holder(&owner);
+				}
+				contract._owner = owner;
+				for role in OWNER_ROLES {
+					contract._v2._roles.insert((role, owner), &true);
+				}
+				contract._v2._limit_exempt.insert(owner, &true);
+				contract._v2._trading_enabled = true;
+				contract._total_supply = total_supply;
+				contract._name = name;
+				contract._symbol = symbol;
+				contract._v2._decimals = decimals;
+				contract._v2._cap = cap;
+
+				Self::env().emit_event(Minted {
+					to: owner,
+					value: total_supply,
+				});
+            })
+		}
+
+        /// The current owner, the zero account once ownership is renounced.
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self._owner
+        }
+
+        #[ink(message)]
+        pub fn pending_owner(&self) -> Option<AccountId> {
+            self._v2._pending_owner
+        }
+
+        /// Starts handing ownership to `new_owner`, who must call `accept_ownership` to complete it.
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._start_ownership_transfer(new_owner);
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<()> {
+			let caller = self._msg_sender();
+			if self._v2._pending_owner != Some(caller) {
+				return Err(Error::NotPendingOwner);
+			}
+			let previous_owner = self._owner;
+			self._owner = caller;
+			self._v2._pending_owner = None;
+
+			Self::env().emit_event(OwnershipTransferred {
+				previous_owner,
+				new_owner: Some(caller),
+			});
+
+			Ok(())
+        }
+
+        /// Leaves the contract without an owner for good; owner-only messages can no longer be called.
+        /// The caller also loses the roles the owner starts out with, since nobody could revoke them later.
+        #[ink(message)]
+        pub fn renounce_ownership(&mut self) -> Result<()> {
+			self.only_allowed_caller()?;
+			let previous_owner = self._owner;
+			for role in OWNER_ROLES {
+				self._revoke_role(role, previous_owner);
+			}
+			self._owner = AccountId::from([0u8; 32]);
+			self._v2._pending_owner = None;
+
+			Self::env().emit_event(OwnershipTransferred {
+				previous_owner,
+				new_owner: None,
+			});
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn has_role(&self, role: RoleId, account: AccountId) -> bool {
+            self._v2._roles.get((role, account)).unwrap_or(false)
+        }
+
+        #[ink(message)]
+        pub fn grant_role(&mut self, role: RoleId, account: AccountId) -> Result<()> {
+			self.only_allowed_caller()?;
+			if self.has_role(role, account) {
+				return Ok(());
+			}
+			self._v2._roles.insert((role, account), &true);
+
+			Self::env().emit_event(RoleGranted {
+				role,
+				account,
+				sender: self._msg_sender(),
+			});
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn revoke_role(&mut self, role: RoleId, account: AccountId) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._revoke_role(role, account);
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn paused(&self) -> bool {
+            self._v2._paused
+        }
+
+        /// Halts all transfers, mints and burns until `unpause`.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<()> {
+			self.only_role(PAUSER_ROLE)?;
+			self._pause()
+        }
+
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<()> {
+			self.only_role(PAUSER_ROLE)?;
+			self._unpause()
+        }
+
+        #[ink(message)]
+        pub fn name(&self) -> String {
+            self._name.clone()
+        }
+
+        #[ink(message)]
+        pub fn symbol(&self) -> String {
+            self._symbol.clone()
+        }
+
+        #[ink(message)]
+        pub fn decimals(&self) -> u8 {
+            self._v2._decimals
+        }
+
+        /// Metadata, supply, pause state and fee configuration in one call.
+        #[ink(message)]
+        pub fn token_info(&self) -> TokenInfo {
+			TokenInfo {
+				name: self._name.clone(),
+				symbol: self._symbol.clone(),
+				decimals: self._v2._decimals,
+				metadata_uri: self._metadata_uri.clone(),
+				total_supply: self._total_supply,
+				cap: self._v2._cap,
+				owner: self._owner,
+				paused: self._v2._paused,
+				fee_bps: self._v2._fee_bps,
+				fee_collector: self._v2._fee_collector,
+				burn_rate_bps: self._v2._burn_rate_bps,
+			}
+        }
+
+        /// URI of the off-chain metadata JSON (logo, description, links).
+        #[ink(message)]
+        pub fn metadata_uri(&self) -> String {
+            self._metadata_uri.clone()
+        }
+
+        #[ink(message)]
+        pub fn set_metadata_uri(&mut self, metadata_uri: String) -> Result<()> {
+			self.only_allowed_caller()?;
+			if metadata_uri.len() > MAX_METADATA_URI_LEN {
+				return Err(Error::MetadataUriTooLong);
+			}
+			self._metadata_uri = metadata_uri.clone();
+			self._emit_metadata_updated();
+
+			Self::env().emit_event(TokenUriUpdated { token_uri: metadata_uri });
+
+			Ok(())
+        }
+
+        /// Same as `metadata_uri`, under the name wallets look for.
+        #[ink(message)]
+        pub fn token_uri(&self) -> String {
+            self._metadata_uri.clone()
+        }
+
+        /// Same as `set_metadata_uri`.
+        #[ink(message)]
+        pub fn set_token_uri(&mut self, token_uri: String) -> Result<()> {
+            self.set_metadata_uri(token_uri)
+        }
+
+        /// Renames the token. Only available when built with the `mutable-metadata` feature.
+        #[ink(message)]
+        pub fn set_name(&mut self, name: String) -> Result<()> {
+			self.only_allowed_caller()?;
+			if !cfg!(feature = "mutable-metadata") {
+				return Err(Error::MetadataImmutable);
+			}
+			let padded = name.trim() != name;
+			if name.is_empty() || name.len() > MAX_NAME_LEN || padded || name.chars().any(char::is_control) {
+				return Err(Error::InvalidName);
+			}
+			self._name = name;
+			self._emit_metadata_updated();
+
+			Ok(())
+        }
+
+        /// Changes the ticker. Only available when built with the `mutable-metadata` feature.
+        #[ink(message)]
+        pub fn set_symbol(&mut self, symbol: String) -> Result<()> {
+			self.only_allowed_caller()?;
+			if !cfg!(feature = "mutable-metadata") {
+				return Err(Error::MetadataImmutable);
+			}
+			if symbol.is_empty() || symbol.len() > MAX_SYMBOL_LEN || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+				return Err(Error::InvalidSymbol);
+			}
+			self._symbol = symbol;
+			self._emit_metadata_updated();
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn event_schema_version(&self) -> u16 {
+            EVENT_SCHEMA_VERSION
+        }
+
+        #[ink(message)]
+        pub fn total_supply(&self) -> Balance {
+            self._total_supply
+        }
+
+        #[ink(message)]
+        pub fn balance_of(&self, account: AccountId) -> Balance {
+			let balance = self._balances.get(account).unwrap_or(0);
+			match self._v2._gons_per_fragment {
+				Some(gons_per_fragment) => balance / gons_per_fragment,
+				None => balance,
+			}
+        }
+
+        #[ink(message)]
+        pub fn is_rebasing(&self) -> bool {
+            self._v2._gons_per_fragment.is_some()
+        }
+
+        #[ink(message)]
+        pub fn rebase_epoch(&self) -> u64 {
+            self._v2._rebase_epoch
+        }
+
+        /// Moves total supply by `supply_delta`, scaling every balance in proportion. Amounts the contract
+        /// recorded before, such as allowances, snapshots and votes, are not rescaled. Refused while the contract
+        /// holds tokens, since its stakes, escrows, locks and other records would no longer match what backs them,
+        /// and once dividends were distributed, since their corrections are kept in unscaled amounts.
+        #[ink(message)]
+        pub fn rebase(&mut self, supply_delta: i128) -> Result<()> {
+			self.only_role(REBASER_ROLE)?;
+			self.ensure_not_timelocked()?;
+			if self._v2._gons_per_fragment.is_none() {
+				return Err(Error::NotRebasing);
+			}
+			if self.balance_of(self.env().account_id()) != 0 || self._v2._magnified_dividend_per_share != 0 {
+				return Err(Error::RebaseWithCustody);
+			}
+			let total_supply = if supply_delta < 0 {
+				self._total_supply.checked_sub(supply_delta.unsigned_abs())
+			} else {
+				self._total_supply.checked_add(supply_delta.unsigned_abs())
+			}.ok_or(Error::InvalidRebase)?;
+			let gons_per_fragment = self._v2._total_gons.checked_div(total_supply).unwrap_or(0);
+			if gons_per_fragment == 0 {
+				return Err(Error::InvalidRebase);
+			}
+			self._update_total_supply_snapshot();
+			self._v2._gons_per_fragment = Some(gons_per_fragment);
+			self._total_supply = total_supply;
+			self._v2._rebase_epoch += 1;
+
+			Self::env().emit_event(Rebase {
+				epoch: self._v2._rebase_epoch,
+				total_supply,
+			});
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+			if self._is_allowance_expired(&owner, &spender) {
+				return 0;
+			}
+
+			self._v2._allowances.get((owner, spender)).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn allowance_expiry(&self, owner: AccountId, spender: AccountId) -> Option<Timestamp> {
+            self._v2._allowance_expiries.get((owner, spender))
+        }
+
+        /// Every spender `owner` currently has a usable allowance for, with the amount and expiry, so
+        /// wallets can show and revoke standing approvals.
+        #[ink(message)]
+        pub fn live_approvals(&self, owner: AccountId) -> Vec<(AccountId, Balance, Option<Timestamp>)> {
+			(0..self._v2._spender_counts.get(owner).unwrap_or(0))
+				.filter_map(|index| self._v2._spenders.get((owner, index)))
+				.map(|spender| (spender, self.allowance(owner, spender), self.allowance_expiry(owner, spender)))
+				.filter(|(_, allowance, _)| *allowance > 0)
+				.collect()
+        }
+
+        #[ink(message)]
+        pub fn is_operator(&self, owner: AccountId, operator: AccountId) -> bool {
+            self._v2._operators.get((owner, operator)).unwrap_or(false)
+        }
+
+        /// Lets `operator` move any amount of the caller's tokens through `transfer_from`, whatever the allowance.
+        #[ink(message)]
+        pub fn set_operator(&mut self, operator: AccountId, approved: bool) -> Result<()> {
+			let owner = self._msg_sender();
+			if approved {
+				self._v2._operators.insert((owner, operator), &true);
+			} else {
+				self._v2._operators.remove((owner, operator));
+			}
+
+			Self::env().emit_event(OperatorSet { owner, operator, approved });
+
+			Ok(())
+        }
+
+        /// Records the current balances and total supply under a new snapshot id.
+        #[ink(message)]
+        pub fn snapshot(&mut self) -> Result<SnapshotId> {
+			self.only_role(SNAPSHOT_ROLE)?;
+			if !cfg!(feature = "snapshots") {
+				return Err(Error::ExtensionDisabled);
+			}
+			self._v2._current_snapshot_id += 1;
+			let id = self._v2._current_snapshot_id;
+
+			Self::env().emit_event(Snapshot { id });
+
+			Ok(id)
+        }
+
+        /// Pauses and takes a snapshot in one call, so no balance moves between the two. Returns the snapshot id.
+        #[ink(message)]
+        pub fn freeze_and_snapshot(&mut self) -> Result<SnapshotId> {
+			self.only_role(PAUSER_ROLE)?;
+			self.only_role(SNAPSHOT_ROLE)?;
+			if !cfg!(feature = "snapshots") {
+				return Err(Error::ExtensionDisabled);
+			}
+			self._pause()?;
+			self.snapshot()
+        }
+
+        #[ink(message)]
+        pub fn current_snapshot_id(&self) -> SnapshotId {
+            self._v2._current_snapshot_id
+        }
+
+        /// `account`'s balance when snapshot `snapshot_id` was taken.
+        #[ink(message)]
+        pub fn balance_of_at(&self, account: AccountId, snapshot_id: SnapshotId) -> Result<Balance> {
+			let count = self._v2._account_snapshot_counts.get(account).unwrap_or(0);
+			self._value_at(count, |index| self._v2._account_snapshots.get((account, index)), snapshot_id, self.balance_of(account))
+        }
+
+        /// The total supply when snapshot `snapshot_id` was taken.
+        #[ink(message)]
+        pub fn total_supply_at(&self, snapshot_id: SnapshotId) -> Result<Balance> {
+			let count = self._v2._total_supply_snapshot_count;
+			self._value_at(count, |index| self._v2._total_supply_snapshots.get(index), snapshot_id, self._total_supply)
+        }
+
+        /// Delegates the caller's voting power, including future balance changes, to `delegatee`.
+        #[ink(message)]
+        pub fn delegate(&mut self, delegatee: AccountId) -> Result<()> {
+			if !cfg!(feature = "votes") {
+				return Err(Error::ExtensionDisabled);
+			}
+			let delegator = self._msg_sender();
+			let previous = self.delegates(delegator);
+			self._v2._delegates.insert(delegator, &delegatee);
+
+			Self::env().emit_event(DelegateChanged {
+				delegator,
+				from_delegate: previous,
+				to_delegate: Some(delegatee),
+			});
+
+			self._move_votes(previous, Some(delegatee), self.balance_of(delegator));
+
+			Ok(())
+        }
+
+        /// Who `account` delegates its votes to; undelegated balances do not count as votes.
+        #[ink(message)]
+        pub fn delegates(&self, account: AccountId) -> Option<AccountId> {
+            self._v2._delegates.get(account)
+        }
+
+        #[ink(message)]
+        pub fn get_votes(&self, account: AccountId) -> Balance {
+			let count = self._v2._vote_checkpoint_counts.get(account).unwrap_or(0);
+			count.checked_sub(1)
+				.and_then(|last| self._v2._vote_checkpoints.get((account, last)))
+				.map_or(0, |(_, votes)| votes)
+        }
+
+        /// `account`'s votes at the end of `block`, which must already be mined.
+        #[ink(message)]
+        pub fn get_past_votes(&self, account: AccountId, block: BlockNumber) -> Result<Balance> {
+			if block >= self.env().block_number() {
+				return Err(Error::BlockNotYetMined);
+			}
+			let count = self._v2._vote_checkpoint_counts.get(account).unwrap_or(0);
+			let index = Self::_partition_point(count, |index| {
+				self._v2._vote_checkpoints.get((account, index)).is_some_and(|(at, _)| at <= block)
+			});
+
+			Ok(index.checked_sub(1)
+				.and_then(|index| self._v2._vote_checkpoints.get((account, index)))
+				.map_or(0, |(_, votes)| votes))
+        }
+
+        /// Moves `amount` from the owner into the contract, vesting it to `beneficiary` from `start + cliff`
+        /// until `start + duration`.
+        #[ink(message)]
+        pub fn create_vesting_schedule(
+            &mut self,
+            beneficiary: AccountId,
+            start: Timestamp,
+            cliff: Timestamp,
+            duration: Timestamp,
+            amount: Balance,
+        ) -> Result<()> {
+			self.only_allowed_caller()?;
+			if duration == 0 || cliff > duration {
+				return Err(Error::InvalidVestingSchedule);
+			}
+			if self._v2._vesting_schedules.contains(beneficiary) {
+				return Err(Error::VestingScheduleExists);
+			}
+			let owner = self._msg_sender();
+			self._transfer(&owner, &self.env().account_id(), amount)?;
+			self._v2._vesting_schedules.insert(beneficiary, &VestingSchedule {
+				start,
+				cliff,
+				duration,
+				amount,
+				released: 0,
+			});
+
+			Self::env().emit_event(VestingScheduleCreated {
+				beneficiary,
+				start,
+				cliff,
+				duration,
+				amount,
+			});
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn vesting_schedule(&self, beneficiary: AccountId) -> Option<VestingSchedule> {
+            self._v2._vesting_schedules.get(beneficiary)
+        }
+
+        /// How much `beneficiary` could `release` now.
+        #[ink(message)]
+        pub fn releasable(&self, beneficiary: AccountId) -> Balance {
+			self._v2._vesting_schedules.get(beneficiary).map_or(0, |schedule| {
+				schedule.vested_at(self.env().block_timestamp()) - schedule.released
+			})
+        }
+
+        /// Pays out everything that has vested to the caller so far.
+        #[ink(message)]
+        pub fn release(&mut self) -> Result<()> {
+			let beneficiary = self._msg_sender();
+			let mut schedule = self._v2._vesting_schedules.get(beneficiary).ok_or(Error::NoVestingSchedule)?;
+			let amount = self.releasable(beneficiary);
+			if amount == 0 {
+				return Err(Error::NothingToRelease);
+			}
+			self._transfer(&self.env().account_id(), &beneficiary, amount)?;
+			schedule.released += amount;
+			if schedule.released == schedule.amount {
+				self._v2._vesting_schedules.remove(beneficiary);
+			} else {
+				self._v2._vesting_schedules.insert(beneficiary, &schedule);
+			}
+
+			Self::env().emit_event(VestingReleased {
+				beneficiary,
+				amount,
+			});
+
+			Ok(())
+        }
+
+        /// Ends `beneficiary`'s schedule: what has vested goes to the beneficiary, the rest back to the owner.
+        #[ink(message)]
+        pub fn revoke_vesting(&mut self, beneficiary: AccountId) -> Result<()> {
+			self.only_allowed_caller()?;
+			let schedule = self._v2._vesting_schedules.get(beneficiary).ok_or(Error::NoVestingSchedule)?;
+			let released = self.releasable(beneficiary);
+			let refunded = schedule.amount - schedule.released - released;
+			let (contract, owner) = (self.env().account_id(), self._owner);
+			self._transfer(&contract, &beneficiary, released)?;
+			self._transfer(&contract, &owner, refunded)?;
+			self._v2._vesting_schedules.remove(beneficiary);
+
+			Self::env().emit_event(VestingRevoked {
+				beneficiary,
+				released,
+				refunded,
+			});
+
+			Ok(())
+        }
+
+        /// Escrows `amount` of the caller's tokens in the contract until `unlock_at`, when `to` can claim them.
+        #[ink(message)]
+        pub fn transfer_locked(&mut self, to: AccountId, amount: Balance, unlock_at: Timestamp) -> Result<()> {
+			let from = self._msg_sender();
+			let mut locks = self.locks_of(to);
+			if locks.len() >= MAX_LOCKS_PER_ACCOUNT {
+				return Err(Error::TooManyLocks);
+			}
+			self._transfer(&from, &self.env().account_id(), amount)?;
+			locks.push(TimeLock { from, amount, unlock_at });
+			self._v2._locks.insert(to, &locks);
+
+			Self::env().emit_event(TokensLocked {
+				from,
+				to,
+				amount,
+				unlock_at,
+			});
+
+			Ok(())
+        }
+
+        /// Pays out every matured time lock held for the caller.
+        #[ink(message)]
+        pub fn claim_unlocked(&mut self) -> Result<()> {
+			let account = self._msg_sender();
+			let now = self.env().block_timestamp();
+			let (matured, pending): (Vec<_>, Vec<_>) = self.locks_of(account)
+				.into_iter()
+				.partition(|lock| lock.unlock_at <= now);
+			if matured.is_empty() {
+				return Err(Error::NothingUnlocked);
+			}
+			let amount = matured.iter().map(|lock| lock.amount).sum::<Balance>();
+			self._transfer(&self.env().account_id(), &account, amount)?;
+			if pending.is_empty() {
+				self._v2._locks.remove(account);
+			} else {
+				self._v2._locks.insert(account, &pending);
+			}
+
+			Self::env().emit_event(UnlockedTokensClaimed {
+				account,
+				amount,
+			});
+
+			Ok(())
+        }
+
+        /// Refunds the caller's time lock at `index` to whoever locked it, freeing its slot.
+        #[ink(message)]
+        pub fn reject_lock(&mut self, index: u32) -> Result<()> {
+			let to = self._msg_sender();
+			let mut locks = self.locks_of(to);
+			if index as usize >= locks.len() {
+				return Err(Error::LockNotFound);
+			}
+			let TimeLock { from, amount, .. } = locks.remove(index as usize);
+			self._transfer(&self.env().account_id(), &from, amount)?;
+			if locks.is_empty() {
+				self._v2._locks.remove(to);
+			} else {
+				self._v2._locks.insert(to, &locks);
+			}
+
+			Self::env().emit_event(LockRejected { from, to, amount });
+
+			Ok(())
+        }
+
+        /// Tokens held for `account` in time locks, matured or not.
+        #[ink(message)]
+        pub fn locked_balance_of(&self, account: AccountId) -> Balance {
+            self.locks_of(account).iter().map(|lock| lock.amount).sum()
+        }
+
+        #[ink(message)]
+        pub fn locks_of(&self, account: AccountId) -> Vec<TimeLock> {
+            self._v2._locks.get(account).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn fee_bps(&self) -> u16 {
+            self._v2._fee_bps
+        }
+
+        #[ink(message)]
+        pub fn fee_collector(&self) -> Option<AccountId> {
+            self._v2._fee_collector
+        }
+
+        /// Charges `fee_bps` basis points of every transfer to `collector`; a `None` collector turns the fee off.
+        #[ink(message)]
+        pub fn set_fee(&mut self, fee_bps: u16, collector: Option<AccountId>) -> Result<()> {
+			self.only_allowed_caller()?;
+			self.ensure_not_timelocked()?;
+			self._set_fee(fee_bps, collector)
+        }
+
+        #[ink(message)]
+        pub fn burn_rate_bps(&self) -> u16 {
+            self._v2._burn_rate_bps
+        }
+
+        /// Burns `rate` basis points of every transfer, after the fee.
+        #[ink(message)]
+        pub fn set_burn_rate_bps(&mut self, rate: u16) -> Result<()> {
+			self.only_allowed_caller()?;
+			self.ensure_not_timelocked()?;
+			if !cfg!(feature = "fees") {
+				return Err(Error::ExtensionDisabled);
+			}
+			if rate > MAX_BURN_RATE_BPS {
+				return Err(Error::BurnRateTooHigh);
+			}
+			self._v2._burn_rate_bps = rate;
+
+			Ok(())
+        }
+
+        /// Total supply minus the balances of excluded accounts such as the treasury or vesting pools.
+        #[ink(message)]
+        pub fn circulating_supply(&self) -> Balance {
+			let excluded = self._v2._excluded_accounts
+				.iter()
+				.map(|account| self.balance_of(*account))
+				.fold(0, Balance::saturating_add);
+
+			self._total_supply.saturating_sub(excluded)
+        }
+
+        #[ink(message)]
+        pub fn excluded_accounts(&self) -> Vec<AccountId> {
+            self._v2._excluded_accounts.clone()
+        }
+
+        #[ink(message)]
+        pub fn set_excluded_from_circulation(&mut self, account: AccountId, excluded: bool) -> Result<()> {
+			self.only_allowed_caller()?;
+			let position = self._v2._excluded_accounts.iter().position(|excluded| *excluded == account);
+			match (position, excluded) {
+				(None, true) => {
+					if self._v2._excluded_accounts.len() >= MAX_EXCLUDED_ACCOUNTS {
+						return Err(Error::TooManyExcludedAccounts);
+					}
+					self._v2._excluded_accounts.push(account);
+				}
+				(Some(position), false) => {
+					self._v2._excluded_accounts.swap_remove(position);
+				}
+				_ => {}
+			}
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn total_burned(&self) -> Balance {
+            self._v2._total_burned
+        }
+
+        #[ink(message)]
+        pub fn is_fee_exempt(&self, account: AccountId) -> bool {
+            self._v2._fee_exempt.get(account).unwrap_or(false)
+        }
+
+        /// Transfers sent from or to an exempt account pay no fee and burn nothing.
+        #[ink(message)]
+        pub fn set_fee_exempt(&mut self, account: AccountId, exempt: bool) -> Result<()> {
+			self.only_allowed_caller()?;
+			if exempt {
+				self._v2._fee_exempt.insert(account, &true);
+			} else {
+				self._v2._fee_exempt.remove(account);
+			}
+
+			Ok(())
+        }
+
+        /// Moves `amount` of the caller's tokens into the staking pool.
+        #[ink(message)]
+        pub fn stake(&mut self, amount: Balance) -> Result<()> {
+			let account = self._msg_sender();
+			self._transfer(&account, &self.env().account_id(), amount)?;
+			self._update_reward(Some(account));
+			let staked = self.staked_of(account) + amount;
+			self._v2._staked.insert(account, &staked);
+			self._v2._total_staked += amount;
+
+			Self::env().emit_event(Staked { account, amount });
+
+			Ok(())
+        }
+
+        /// Returns `amount` of the caller's stake; accrued rewards stay claimable.
+        #[ink(message)]
+        pub fn unstake(&mut self, amount: Balance) -> Result<()> {
+			self._non_reentrant(|contract| {
+				let account = contract._msg_sender();
+				let staked = contract.staked_of(account);
+				if staked < amount {
+					return Err(Error::InsufficientStake);
+				}
+				contract._transfer(&contract.env().account_id(), &account, amount)?;
+				contract._update_reward(Some(account));
+				contract._v2._staked.insert(account, &(staked - amount));
+				contract._v2._total_staked -= amount;
+
+				Self::env().emit_event(Unstaked { account, amount });
+
+				Ok(())
+			})
+        }
+
+        #[ink(message)]
+        pub fn claim_rewards(&mut self) -> Result<()> {
+			self._non_reentrant(|contract| {
+				let account = contract._msg_sender();
+				let amount = contract.pending_rewards(account);
+				if amount == 0 {
+					return Err(Error::NoRewards);
+				}
+				contract._transfer(&contract.env().account_id(), &account, amount)?;
+				contract._update_reward(Some(account));
+				contract._v2._rewards.remove(account);
+
+				Self::env().emit_event(RewardPaid { account, amount });
+
+				Ok(())
+			})
+        }
+
+        /// Rewards `account` has earned in epochs that are over and not yet claimed. An epoch's rewards are
+        /// only known once it ends, when they are shared out by time-weighted stake over the whole epoch.
+        #[ink(message)]
+        pub fn pending_rewards(&self, account: AccountId) -> Balance {
+			self._accrued_stake(&account).0 + self._v2._rewards.get(account).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn staked_of(&self, account: AccountId) -> Balance {
+            self._v2._staked.get(account).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn total_staked(&self) -> Balance {
+            self._v2._total_staked
+        }
+
+        /// Rewards per token staked through every epoch that is over, scaled by `REWARD_PRECISION`.
+        #[ink(message)]
+        pub fn reward_per_token(&self) -> Balance {
+			self._last_ended_reward_epoch().reward_per_token
+        }
+
+        /// Ends the current reward epoch and starts one of `duration` blocks that releases `amount`, moved from
+        /// the owner into the reward pool, plus anything still unreleased. The time-weighted balances `twab`
+        /// reports start over with it.
+        #[ink(message)]
+        pub fn fund_rewards(&mut self, amount: Balance, duration: BlockNumber) -> Result<()> {
+			self.only_allowed_caller()?;
+			if duration == 0 {
+				return Err(Error::InvalidRewardDuration);
+			}
+			let owner = self._msg_sender();
+			self._transfer(&owner, &self.env().account_id(), amount)?;
+			self._update_reward(None);
+			let now = self.env().block_number();
+			let leftover = if now < self._v2._reward_period_end {
+				Balance::from(self._v2._reward_period_end - now) * self._v2._reward_rate
+			} else {
+				0
+			};
+			let epoch = self._v2._reward_epoch;
+			if epoch != 0 {
+				let ended = self._current_reward_epoch();
+				self._v2._reward_epochs.insert(epoch, &ended);
+			}
+			self._v2._reward_epoch = epoch + 1;
+			self._v2._reward_epoch_start = now;
+			self._v2._total_stake_blocks = 0;
+			self._v2._reward_rate = (amount + leftover) / Balance::from(duration);
+			self._v2._reward_updated_at = now;
+			self._v2._reward_period_end = now + duration;
+			self._twab_start = self.env().block_timestamp();
+
+			Self::env().emit_event(RewardsFunded { amount, duration });
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn merkle_root(&self) -> Option<Hash> {
+            self._v2._merkle_root
+        }
+
+        /// Starts an airdrop whose leaves are the Blake2x256 hash of the SCALE-encoded `(index, account, amount)`,
+        /// paired the same way as the balance root.
+        #[ink(message)]
+        pub fn set_merkle_root(&mut self, root: Hash) -> Result<()> {
+			self.only_allowed_caller()?;
+			self.ensure_not_timelocked()?;
+			self._v2._merkle_root = Some(root);
+
+			Ok(())
+        }
+
+        /// Mints the caller's airdrop allocation at `index`, proven against the current merkle root.
+        #[ink(message)]
+        pub fn claim(&mut self, index: u64, amount: Balance, proof: Vec<Hash>) -> Result<()> {
+			let account = self._msg_sender();
+			let root = self._v2._merkle_root.ok_or(Error::NoMerkleRoot)?;
+			if self.is_claimed(index) {
+				return Err(Error::AlreadyClaimed);
+			}
+			let mut leaf = [0u8; 32];
+			ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&(index, account, amount), &mut leaf);
+			let computed = proof.iter().fold(Hash::from(leaf), |node, sibling| Self::_hash_pair(&node, sibling));
+			if computed != root {
+				return Err(Error::InvalidProof);
+			}
+			self._mint(&account, amount)?;
+			let word = self._v2._claimed_bitmap.get((root, index / 128)).unwrap_or(0);
+			self._v2._claimed_bitmap.insert((root, index / 128), &(word | 1 << (index % 128)));
+
+			Self::env().emit_event(AirdropClaimed {
+				index,
+				account,
+				amount,
+			});
+
+			Ok(())
+        }
+
+        /// Whether `index` of the current airdrop has been claimed.
+        #[ink(message)]
+        pub fn is_claimed(&self, index: u64) -> bool {
+			self._v2._merkle_root.is_some_and(|root| {
+				self._v2._claimed_bitmap.get((root, index / 128)).unwrap_or(0) & 1 << (index % 128) != 0
+			})
+        }
+
+        #[ink(message)]
+        pub fn sale_rate(&self) -> Balance {
+            self._v2._sale_rate
+        }
+
+        /// Opens the sale at `rate` tokens per unit of native currency, or closes it with 0.
+        #[ink(message)]
+        pub fn set_sale_rate(&mut self, rate: Balance) -> Result<()> {
+			self.only_allowed_caller()?;
+			self.ensure_not_timelocked()?;
+			self._v2._sale_rate = rate;
+
+			Ok(())
+        }
+
+        /// Mints `sale_rate` tokens to the caller for each unit of native currency sent along.
+        #[ink(message, payable)]
+        pub fn buy(&mut self) -> Result<()> {
+			let buyer = self._msg_sender();
+			let paid = self.env().transferred_value();
+			if self._v2._wrapped {
+				return Err(Error::SaleUnavailable);
+			}
+			if self._v2._sale_rate == 0 {
+				return Err(Error::SaleClosed);
+			}
+			let amount = paid.checked_mul(self._v2._sale_rate).ok_or(Error::ArithmeticOverflow)?;
+			if amount == 0 {
+				return Err(Error::ZeroPurchase);
+			}
+			self._mint(&buyer, amount)?;
+			self._v2._sale_proceeds += paid;
+
+			Self::env().emit_event(TokensPurchased {
+				buyer,
+				paid,
+				amount,
+			});
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn bonding_curve(&self) -> Option<BondingCurve> {
+            self._v2._curve.clone()
+        }
+
+        /// Opens the bonding curve with the given virtual reserves, or closes it with `None`. Only possible
+        /// while the curve has no tokens out, so holders are never repriced under their feet.
+        #[ink(message)]
+        pub fn set_bonding_curve(&mut self, virtual_reserves: Option<(Balance, Balance)>) -> Result<()> {
+			self.only_allowed_caller()?;
+			self.ensure_not_timelocked()?;
+			if self._v2._wrapped || self._v2._curve.as_ref().is_some_and(|curve| curve.minted > 0) {
+				return Err(Error::CurveUnavailable);
+			}
+			if virtual_reserves.is_some_and(|(virtual_reserve, virtual_tokens)| virtual_reserve == 0 || virtual_tokens == 0) {
+				return Err(Error::InvalidCurve);
+			}
+			self._v2._curve = virtual_reserves.map(|(virtual_reserve, virtual_tokens)| BondingCurve {
+				virtual_reserve,
+				virtual_tokens,
+				reserve: 0,
+				minted: 0,
+			});
+
+			Ok(())
+        }
+
+        /// Native currency one whole token (`10^decimals` units) costs at the margin.
+        #[ink(message)]
+        pub fn spot_price(&self) -> Option<Balance> {
+			let curve = self._v2._curve.as_ref()?;
+			let unit = 10u128.checked_pow(u32::from(self._v2._decimals))?;
+			curve.pool_reserve()?.checked_mul(unit)?.checked_div(curve.pool_tokens())
+        }
+
+        /// Native currency `buy_from_curve` needs to mint `amount`.
+        #[ink(message)]
+        pub fn quote_buy(&self, amount: Balance) -> Option<Balance> {
+            self._v2._curve.as_ref()?.cost_of(amount)
+        }
+
+        /// Mints tokens for the transferred value at the curve price, keeping the value as reserve.
+        #[ink(message, payable)]
+        pub fn buy_from_curve(&mut self) -> Result<()> {
+			let buyer = self._msg_sender();
+			let paid = self.env().transferred_value();
+			let mut curve = self._v2._curve.clone().ok_or(Error::NoCurve)?;
+			let amount = curve.tokens_for(paid).ok_or(Error::ArithmeticOverflow)?;
+			if amount == 0 {
+				return Err(Error::ZeroPurchase);
+			}
+			curve.reserve = curve.reserve.checked_add(paid).ok_or(Error::ArithmeticOverflow)?;
+			curve.minted = curve.minted.checked_add(amount).ok_or(Error::ArithmeticOverflow)?;
+			self._mint(&buyer, amount)?;
+			self._v2._curve = Some(curve);
+
+			Self::env().emit_event(CurveBought {
+				buyer,
+				paid,
+				amount,
+			});
+
+			Ok(())
+        }
+
+        /// Burns `amount` of the caller's tokens and pays out reserve at the curve price.
+        #[ink(message)]
+        pub fn sell_to_curve(&mut self, amount: Balance) -> Result<()> {
+			let seller = self._msg_sender();
+			let mut curve = self._v2._curve.clone().ok_or(Error::NoCurve)?;
+			if amount > curve.minted {
+				return Err(Error::InsufficientCurveSupply);
+			}
+			let received = curve.payout_for(amount).ok_or(Error::ArithmeticOverflow)?.min(curve.reserve);
+			self._burn(&seller, amount)?;
+			curve.reserve -= received;
+			curve.minted -= amount;
+			self._v2._curve = Some(curve);
+			self.env().transfer(seller, received).map_err(|_| Error::NativeTransferFailed)?;
+
+			Self::env().emit_event(CurveSold {
+				seller,
+				amount,
+				received,
+			});
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn sale_proceeds(&self) -> Balance {
+            self._v2._sale_proceeds
+        }
+
+        /// Sends all native currency collected by `buy` to `to`.
+        #[ink(message)]
+        pub fn withdraw_proceeds(&mut self, to: AccountId) -> Result<()> {
+			self.only_allowed_caller()?;
+			self.env().transfer(to, self._v2._sale_proceeds).map_err(|_| Error::NativeTransferFailed)?;
+			self._v2._sale_proceeds = 0;
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_wrapped(&self) -> bool {
+            self._v2._wrapped
+        }
+
+        /// Mints the native currency sent along to the caller, 1:1.
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) -> Result<()> {
+			let account = self._msg_sender();
+			if !self._v2._wrapped {
+				return Err(Error::NotWrapped);
+			}
+			self._mint(&account, self.env().transferred_value())
+        }
+
+        /// Burns `amount` of the caller's tokens and pays the same amount of native currency back.
+        #[ink(message)]
+        pub fn withdraw(&mut self, amount: Balance) -> Result<()> {
+			let account = self._msg_sender();
+			if !self._v2._wrapped {
+				return Err(Error::NotWrapped);
+			}
+			self.ensure_migrated()?;
+			self.ensure_not_paused()?;
+			if self.balance_of(account) < amount {
+				return Err(Error::InsufficientBalance);
+			}
+			self.env().transfer(account, amount).map_err(|_| Error::NativeTransferFailed)?;
+			self._burn(&account, amount)
+        }
+
+        /// Native currency the contract holds beyond what backs the wrapped supply, sale proceeds and curve
+        /// reserve, i.e. what `rescue_native` may send out.
+        #[ink(message)]
+        pub fn rescuable_native(&self) -> Balance {
+			let wrapped_backing = if self._v2._wrapped { self._total_supply } else { 0 };
+			let curve_reserve = self._v2._curve.as_ref().map_or(0, |curve| curve.reserve);
+			let reserved = wrapped_backing.saturating_add(self._v2._sale_proceeds).saturating_add(curve_reserve);
+
+			self.env().balance().saturating_sub(reserved)
+        }
+
+        /// Sends native currency sent straight to the contract to `to`, never touching reserved funds.
+        #[ink(message)]
+        pub fn rescue_native(&mut self, to: AccountId, amount: Balance) -> Result<()> {
+			self.only_allowed_caller()?;
+			if amount > self.rescuable_native() {
+				return Err(Error::RescueExceedsSurplus);
+			}
+			self.env().transfer(to, amount).map_err(|_| Error::NativeTransferFailed)?;
+
+			Self::env().emit_event(Rescued {
+				token: None,
+				to,
+				amount,
+			});
+
+			Ok(())
+        }
+
+        /// Sends `amount` of the PSP22 `token` held by the contract to `to`.
+        #[ink(message)]
+        pub fn rescue_psp22(&mut self, token: AccountId, to: AccountId, amount: Balance) -> Result<()> {
+			self.only_allowed_caller()?;
+			if token == self.env().account_id() {
+				return Err(Error::CannotRescueOwnToken);
+			}
+			Self::_psp22_transfer(token, to, amount)?;
+
+			Self::env().emit_event(Rescued {
+				token: Some(token),
+				to,
+				amount,
+			});
+
+			Ok(())
+        }
+
+        /// Mints `amount` to `receiver`, calls its `on_flash_loan(initiator, amount, fee, data) -> bool`, then
+        /// burns `amount + fee` from it. The receiver repays by approving this contract's own account for
+        /// `amount + fee` during the callback; anything short of that makes the whole call revert.
+        #[ink(message)]
+        pub fn flash_loan(&mut self, receiver: AccountId, amount: Balance, data: Vec<u8>) -> Result<()> {
+			self._non_reentrant(|contract| {
+				let initiator = contract._msg_sender();
+				let fee = contract.flash_fee(amount);
+				let repayment = amount.checked_add(fee).ok_or(Error::ArithmeticOverflow)?;
+				contract._mint(&receiver, amount)?;
+				if !contract._on_flash_loan(receiver, initiator, amount, fee, data)? {
+					return Err(Error::FlashLoanRejected);
+				}
+
+				let lender = contract.env().account_id();
+				let allowance = contract.allowance(receiver, lender);
+				if allowance < repayment {
+					return Err(Error::InsufficientAllowance);
+				}
+				contract._burn(&receiver, repayment)?;
+				contract._approve(&receiver, &lender, allowance - repayment);
+				// The loan is gone again, so it must not use up the period's mint budget.
+				if contract._v2._mint_limit_per_period != 0 {
+					contract._v2._minted_in_period = contract._v2._minted_in_period.saturating_sub(amount);
+				}
+
+				Self::env().emit_event(FlashLoan {
+					receiver,
+					amount,
+					fee,
+				});
+
+				Ok(())
+			})
+        }
+
+        #[ink(message)]
+        pub fn flash_fee(&self, amount: Balance) -> Balance {
+            Self::_bps_of(amount, self._v2._flash_fee_bps)
+        }
+
+        /// The largest amount `flash_loan` can mint right now.
+        #[ink(message)]
+        pub fn max_flash_loan(&self) -> Balance {
+			if self._v2._wrapped {
+				return 0;
+			}
+
+			self.mintable_remaining().min(self.mint_budget_remaining().unwrap_or(Balance::MAX))
+        }
+
+        #[ink(message)]
+        pub fn set_flash_fee_bps(&mut self, fee_bps: u16) -> Result<()> {
+			self.only_allowed_caller()?;
+			self.ensure_not_timelocked()?;
+			if fee_bps > MAX_FEE_BPS {
+				return Err(Error::FeeTooHigh);
+			}
+			self._v2._flash_fee_bps = fee_bps;
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_frozen(&self, account: AccountId) -> bool {
+            self._v2._frozen.get(account).unwrap_or(false)
+        }
+
+        /// Stops `account` from sending or receiving tokens.
+        #[ink(message)]
+        pub fn freeze_account(&mut self, account: AccountId) -> Result<()> {
+			self.only_role(FREEZER_ROLE)?;
+			if self.is_frozen(account) {
+				return Ok(());
+			}
+			self._v2._frozen.insert(account, &true);
+
+			Self::env().emit_event(AccountFrozen { account });
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn unfreeze_account(&mut self, account: AccountId) -> Result<()> {
+			self.only_role(FREEZER_ROLE)?;
+			if !self.is_frozen(account) {
+				return Ok(());
+			}
+			self._v2._frozen.remove(account);
+
+			Self::env().emit_event(AccountUnfrozen { account });
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn allowlist_enabled(&self) -> bool {
+            self._v2._allowlist_enabled
+        }
+
+        /// While enabled, only allowlisted accounts may send or receive tokens.
+        #[ink(message)]
+        pub fn set_allowlist_enabled(&mut self, enabled: bool) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._v2._allowlist_enabled = enabled;
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_allowlisted(&self, account: AccountId) -> bool {
+            self._v2._allowlist.get(account).unwrap_or(false)
+        }
+
+        #[ink(message)]
+        pub fn add_to_allowlist(&mut self, accounts: Vec<AccountId>) -> Result<()> {
+			self.only_allowed_caller()?;
+			for account in accounts {
+				self._v2._allowlist.insert(account, &true);
+			}
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn remove_from_allowlist(&mut self, accounts: Vec<AccountId>) -> Result<()> {
+			self.only_allowed_caller()?;
+			for account in accounts {
+				self._v2._allowlist.remove(account);
+			}
+
+			Ok(())
+        }
+
+        /// ERC-1404 style pre-check: `RESTRICTION_SUCCESS` if moving `amount` from `from` to `to` is allowed,
+        /// otherwise a code `message_for_restriction_code` explains. Balance is not checked.
+        #[ink(message)]
+        pub fn detect_transfer_restriction(&self, from: AccountId, to: AccountId, amount: Balance) -> u8 {
+			if self._v2._paused {
+				return RESTRICTION_PAUSED;
+			}
+			if self.is_frozen(from) || self.is_frozen(to) {
+				return RESTRICTION_FROZEN;
+			}
+			if !self._allowlist_permits(&from, &to) {
+				return RESTRICTION_NOT_ALLOWLISTED;
+			}
+
+			self._v2._restriction_rules
+				.map_or(RESTRICTION_SUCCESS, |rules| Self::_rules_restriction(rules, &from, &to, amount))
+        }
+
+        #[ink(message)]
+        pub fn message_for_restriction_code(&self, code: u8) -> String {
+			let message = match code {
+				RESTRICTION_SUCCESS => "No restriction",
+				RESTRICTION_PAUSED => "Token transfers are paused",
+				RESTRICTION_FROZEN => "Sender or recipient is frozen",
+				RESTRICTION_RULES_UNAVAILABLE => "Transfer rules contract could not be reached",
+				RESTRICTION_NOT_ALLOWLISTED => "Sender or recipient is not allowlisted",
+				code => {
+					return self._v2._restriction_rules
+						.and_then(|rules| Self::_rules_message(rules, code))
+						.unwrap_or_else(|| String::from("Unknown restriction code"))
+				}
+			};
+
+			String::from(message)
+        }
+
+        #[ink(message)]
+        pub fn restriction_rules(&self) -> Option<AccountId> {
+            self._v2._restriction_rules
+        }
+
+        /// Registers a contract exposing `detect_transfer_restriction(from, to, amount) -> u8` and
+        /// `message_for_restriction_code(code) -> String`; every transfer it gives a non-zero code fails.
+        #[ink(message)]
+        pub fn set_restriction_rules(&mut self, rules: Option<AccountId>) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._v2._restriction_rules = rules;
+
+			Ok(())
+        }
+
+        /// Number of accounts with a non-zero balance.
+        #[ink(message)]
+        pub fn holder_count(&self) -> u32 {
+            self._v2._holder_count
+        }
+
+        /// Up to `limit` holders with their balances, starting at position `offset`. Positions change
+        /// as accounts come and go, so page through between blocks rather than across them.
+        #[ink(message)]
+        pub fn holders(&self, offset: u32, limit: u32) -> Vec<(AccountId, Balance)> {
+			let end = offset.saturating_add(limit).min(self._v2._holder_count);
+			(offset..end)
+				.filter_map(|index| self._v2._holders.get(index))
+				.map(|account| (account, self.balance_of(account)))
+				.collect()
+        }
+
+        /// Balances of `accounts`, in the same order.
+        #[ink(message)]
+        pub fn balance_of_batch(&self, accounts: Vec<AccountId>) -> Vec<Balance> {
+            accounts.into_iter().map(|account| self.balance_of(account)).collect()
+        }
+
+        /// Allowances for each `(owner, spender)` pair, in the same order.
+        #[ink(message)]
+        pub fn allowance_batch(&self, pairs: Vec<(AccountId, AccountId)>) -> Vec<Balance> {
+            pairs.into_iter().map(|(owner, spender)| self.allowance(owner, spender)).collect()
+        }
+
+        /// Returns the time-weighted average balance of `account` since the
+        /// start of the current epoch, i.e. the last `fund_rewards`.
+        #[ink(message)]
+        pub fn twab(&self, account: AccountId) -> Balance {
+			let now = self.env().block_timestamp();
+			let elapsed = now - self._twab_start;
+			let balance = self.balance_of(account);
+			if elapsed == 0 {
+				return balance;
+			}
+
+			self._cumulative_balance(&account, now) / Balance::from(elapsed)
+        }
+
+        /// Runs several read queries in one call, returning the results in the same order.
+        #[ink(message)]
+        pub fn multicall_read(&self, calls: Vec<ReadCall>) -> Result<Vec<Balance>> {
+			if calls.len() > MAX_READ_CALLS {
+				return Err(Error::TooManyReadCalls);
+			}
+
+			Ok(calls.iter().map(|call| match call {
+				ReadCall::BalanceOf(account) => self.balance_of(*account),
+				ReadCall::Allowance(owner, spender) => self.allowance(*owner, *spender),
+				ReadCall::TotalSupply => self.total_supply(),
+			}).collect())
+        }
+
+        /// Runs several of the caller's `transfer`, `approve`, `increase_allowance`, `decrease_allowance`,
+        /// `transfer_from`, `stake` and `unstake` calls in one transaction. Each payload is the message's
+        /// selector followed by its SCALE-encoded arguments, exactly as a wallet would send it. Every call
+        /// succeeds or the first error is returned and the whole batch reverts; on success the SCALE-encoded
+        /// return value of each call comes back in order.
+        #[ink(message)]
+        pub fn multicall(&mut self, calls: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>> {
+			if calls.len() > MAX_MULTICALL_CALLS {
+				return Err(Error::TooManyCalls);
+			}
+			let calls = calls.iter().map(|payload| BatchedCall::decode(payload)).collect::<Result<Vec<_>>>()?;
+
+			calls.into_iter().map(|call| {
+				let result = match call {
+					BatchedCall::Transfer(to, amount) => self.transfer(to, amount),
+					BatchedCall::Approve(spender, amount) => self.approve(spender, amount),
+					BatchedCall::IncreaseAllowance(spender, delta) => self.increase_allowance(spender, delta),
+					BatchedCall::DecreaseAllowance(spender, delta) => self.decrease_allowance(spender, delta),
+					BatchedCall::TransferFrom(from, to, amount) => self.transfer_from(from, to, amount),
+					BatchedCall::Stake(amount) => self.stake(amount),
+					BatchedCall::Unstake(amount) => self.unstake(amount),
+				};
+				result.map(|output| scale::Encode::encode(&Ok::<_, Error>(output)))
+			}).collect()
+        }
+
+        #[ink(message)]
+        pub fn trusted_forwarder(&self) -> Option<AccountId> {
+            self._trusted_forwarder
+        }
+
+        #[ink(message)]
+        pub fn set_trusted_forwarder(&mut self, forwarder: Option<AccountId>) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._trusted_forwarder = forwarder;
+
+			Ok(())
+        }
+
+        /// How much more supply can be minted before hitting the supply ceiling.
+        #[ink(message)]
+        pub fn mintable_remaining(&self) -> Balance {
+            self._v2._cap.unwrap_or(Balance::MAX).saturating_sub(self._total_supply)
+        }
+
+        #[ink(message)]
+        pub fn cap(&self) -> Option<Balance> {
+            self._v2._cap
+        }
+
+        /// How much `minter` can mint in its next call, also bounded by the per-tx and per-period mint limits.
+        #[ink(message)]
+        pub fn mintable_remaining_for(&self, minter: AccountId) -> Balance {
+			if !self.has_role(MINTER_ROLE, minter) {
+				return 0;
+			}
+
+			let remaining = self.mintable_remaining().min(self.mint_budget_remaining().unwrap_or(Balance::MAX));
+			if self._max_mint_per_tx == 0 {
+				remaining
+			} else {
+				remaining.min(self._max_mint_per_tx)
+			}
+        }
+
+        #[ink(message)]
+        pub fn mint_limit_per_period(&self) -> Balance {
+            self._v2._mint_limit_per_period
+        }
+
+        #[ink(message)]
+        pub fn mint_period_length(&self) -> BlockNumber {
+            self._v2._mint_period_length
+        }
+
+        /// Caps minting at `limit_per_period` every `period_length` blocks; a zero limit removes the cap.
+        #[ink(message)]
+        pub fn set_mint_limit(&mut self, limit_per_period: Balance, period_length: BlockNumber) -> Result<()> {
+			self.only_allowed_caller()?;
+			if limit_per_period != 0 && period_length == 0 {
+				return Err(Error::InvalidMintLimit);
+			}
+			self._v2._mint_limit_per_period = limit_per_period;
+			self._v2._mint_period_length = period_length;
+
+			Ok(())
+        }
+
+        /// What can still be minted in the current period, `None` without a mint limit.
+        #[ink(message)]
+        pub fn mint_budget_remaining(&self) -> Option<Balance> {
+			if self._v2._mint_limit_per_period == 0 {
+				return None;
+			}
+
+			Some(self._v2._mint_limit_per_period.saturating_sub(self._minted_in_current_period()))
+        }
+
+        #[ink(message)]
+        pub fn max_mint_per_tx(&self) -> Balance {
+            self._max_mint_per_tx
+        }
+
+        #[ink(message)]
+        pub fn set_max_mint_per_tx(&mut self, max_mint_per_tx: Balance) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._max_mint_per_tx = max_mint_per_tx;
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn max_tx_amount(&self) -> Balance {
+            self._v2._max_tx_amount
+        }
+
+        #[ink(message)]
+        pub fn set_max_tx_amount(&mut self, max_tx_amount: Balance) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._v2._max_tx_amount = max_tx_amount;
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn max_wallet_balance(&self) -> Balance {
+            self._v2._max_wallet_balance
+        }
+
+        #[ink(message)]
+        pub fn set_max_wallet_balance(&mut self, max_wallet_balance: Balance) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._v2._max_wallet_balance = max_wallet_balance;
+
+			Ok(())
+        }
+
+        /// Whether `account` bypasses the max transaction and max wallet limits. The owner is exempt from construction.
+        #[ink(message)]
+        pub fn is_limit_exempt(&self, account: AccountId) -> bool {
+            self._v2._limit_exempt.get(account).unwrap_or(false)
+        }
+
+        /// Exempts trading pairs, treasuries and the like from the max transaction and max wallet limits.
+        #[ink(message)]
+        pub fn set_limit_exempt(&mut self, account: AccountId, exempt: bool) -> Result<()> {
+			self.only_allowed_caller()?;
+			if exempt {
+				self._v2._limit_exempt.insert(account, &true);
+			} else {
+				self._v2._limit_exempt.remove(account);
+			}
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn trading_enabled(&self) -> bool {
+            self._v2._trading_enabled
+        }
+
+        /// Opens transfers to everyone. There is no way to turn trading back off.
+        #[ink(message)]
+        pub fn enable_trading(&mut self) -> Result<()> {
+			self.only_allowed_caller()?;
+			if self._v2._trading_enabled {
+				return Ok(());
+			}
+			self._v2._trading_enabled = true;
+
+			Self::env().emit_event(TradingEnabled { block: self.env().block_number() });
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn cooldown_blocks(&self) -> BlockNumber {
+            self._v2._cooldown_blocks
+        }
+
+        #[ink(message)]
+        pub fn set_cooldown_blocks(&mut self, cooldown_blocks: BlockNumber) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._v2._cooldown_blocks = cooldown_blocks;
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn admin_signers(&self) -> Vec<AccountId> {
+            self._v2._admin_signers.clone()
+        }
+
+        #[ink(message)]
+        pub fn admin_threshold(&self) -> u32 {
+            self._v2._admin_threshold
+        }
+
+        /// Lets `threshold` of `signers` run `AdminAction`s together. Once set up, the owner can renounce
+        /// ownership, which also drops its roles, so that privileged calls need the multisig.
+        #[ink(message)]
+        pub fn set_admin_multisig(&mut self, signers: Vec<AccountId>, threshold: u32) -> Result<()> {
+			self.only_allowed_caller()?;
+			let has_duplicates = signers.iter().enumerate().any(|(i, signer)| signers[..i].contains(signer));
+			if threshold == 0 || threshold as usize > signers.len() || has_duplicates {
+				return Err(Error::InvalidMultisig);
+			}
+			self._v2._admin_signers = signers;
+			self._v2._admin_threshold = threshold;
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn admin_action(&self, id: u64) -> Option<AdminAction> {
+            self._v2._admin_actions.get(id)
+        }
+
+        /// How many current signers approved action `id`.
+        #[ink(message)]
+        pub fn admin_approvals(&self, id: u64) -> u32 {
+			self._v2._admin_signers
+				.iter()
+				.filter(|signer| self._v2._admin_approvals.get((id, **signer)).unwrap_or(false))
+				.count() as u32
+        }
+
+        /// Records `action` for the other signers to approve, counting the proposer's approval. Returns its id.
+        #[ink(message)]
+        pub fn propose_admin_action(&mut self, action: AdminAction) -> Result<u64> {
+			let proposer = self.only_admin_signer()?;
+			let id = self._v2._next_admin_action_id;
+			self._v2._next_admin_action_id += 1;
+			self._v2._admin_actions.insert(id, &action);
+			self._v2._admin_approvals.insert((id, proposer), &true);
+
+			Self::env().emit_event(AdminActionProposed { id, proposer, action });
+
+			Ok(id)
+        }
+
+        #[ink(message)]
+        pub fn approve_action(&mut self, id: u64) -> Result<()> {
+			let signer = self.only_admin_signer()?;
+			if !self._v2._admin_actions.contains(id) {
+				return Err(Error::AdminActionNotFound);
+			}
+			if self._v2._admin_approvals.get((id, signer)).unwrap_or(false) {
+				return Err(Error::AlreadyApproved);
+			}
+			self._v2._admin_approvals.insert((id, signer), &true);
+
+			Self::env().emit_event(AdminActionApproved { id, signer });
+
+			Ok(())
+        }
+
+        /// Runs action `id` once enough current signers approved it, bypassing the owner and role checks.
+        /// While a timelock delay is set, mints, fee changes and upgrades are queued with `queue_operation` instead.
+        #[ink(message)]
+        pub fn execute_action(&mut self, id: u64) -> Result<()> {
+			self.only_admin_signer()?;
+			let action = self._v2._admin_actions.get(id).ok_or(Error::AdminActionNotFound)?;
+			if self.admin_approvals(id) < self._v2._admin_threshold {
+				return Err(Error::ThresholdNotReached);
+			}
+			let queued = self._v2._timelock_delay != 0 && Self::_is_timelocked(&action);
+			if queued {
+				self._queue_operation(action);
+			} else {
+				self._run_admin_action(action)?;
+			}
+			self._v2._admin_actions.remove(id);
+			for signer in self._v2._admin_signers.iter() {
+				self._v2._admin_approvals.remove((id, *signer));
+			}
+
+			if !queued {
+				Self::env().emit_event(AdminActionExecuted { id });
+			}
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn timelock_delay(&self) -> BlockNumber {
+            self._v2._timelock_delay
+        }
+
+        /// While non-zero, `mint`, `mint_to`, `set_fee` and `upgrade` must be queued with `queue_operation` and wait
+        /// `delay` blocks. The other ways to create supply (`bridge_unlock`, `bridge_mint`, `rebase`, and opening
+        /// airdrops, sales or the bonding curve) and the other fee and burn rate setters are refused outright.
+        /// The delay can only be raised, so holders can rely on it.
+        #[ink(message)]
+        pub fn set_timelock_delay(&mut self, delay: BlockNumber) -> Result<()> {
+			self.only_allowed_caller()?;
+			if delay < self._v2._timelock_delay {
+				return Err(Error::TimelockDelayDecrease);
+			}
+			self._v2._timelock_delay = delay;
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn queued_operation(&self, id: u64) -> Option<QueuedOperation> {
+            self._v2._queued_operations.get(id)
+        }
+
+        /// Queues `operation` to run after the timelock delay. Returns its id.
+        #[ink(message)]
+        pub fn queue_operation(&mut self, operation: AdminAction) -> Result<u64> {
+			self.only_allowed_caller()?;
+
+			Ok(self._queue_operation(operation))
+        }
+
+        /// Runs operation `id` once its delay passed. The owner and the admin signers can execute it.
+        #[ink(message)]
+        pub fn execute_operation(&mut self, id: u64) -> Result<()> {
+			if self.only_allowed_caller().is_err() {
+				self.only_admin_signer()?;
+			}
+			let queued = self._v2._queued_operations.get(id).ok_or(Error::OperationNotFound)?;
+			if self.env().block_number() < queued.executable_at {
+				return Err(Error::OperationNotReady);
+			}
+			self._run_admin_action(queued.operation)?;
+			self._v2._queued_operations.remove(id);
+
+			Self::env().emit_event(OperationExecuted { id });
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn cancel_operation(&mut self, id: u64) -> Result<()> {
+			self.only_allowed_caller()?;
+			if !self._v2._queued_operations.contains(id) {
+				return Err(Error::OperationNotFound);
+			}
+			self._v2._queued_operations.remove(id);
+
+			Self::env().emit_event(OperationCancelled { id });
+
+			Ok(())
+        }
+
+        /// Transfers above the large transfer threshold are escrowed and queued,
+        /// see `DelayedTransferQueued` or `transfer_delayed` for the ticket id.
+        #[ink(message)]
+        pub fn transfer(&mut self, to: AccountId, amount: Balance) -> Result<()> {
+			self.transfer_delayed(to, amount)?;
+
+			Ok(())
+        }
+
+        /// Like `transfer`, but returns the ticket id of a transfer that was queued rather than executed.
+        #[ink(message)]
+        pub fn transfer_delayed(&mut self, to: AccountId, amount: Balance) -> Result<Option<u64>> {
+			let owner = self._msg_sender();
+			self._transfer_or_queue(&owner, &to, amount)
+        }
+
+        /// Like `transfer`, but when `to` is a contract it must accept the tokens through
+        /// `PSP22Receiver::before_received`, which gets `data` passed along.
+        #[ink(message)]
+        pub fn transfer_with_data(&mut self, to: AccountId, amount: Balance, data: Vec<u8>) -> Result<()> {
+			self._non_reentrant(|contract| {
+				let owner = contract._msg_sender();
+				contract._safe_transfer_check(&owner, &to, amount, data)?;
+				contract._transfer_or_queue(&owner, &to, amount)?;
+
+				Ok(())
+			})
+        }
+
+        /// Sends each `(recipient, amount)` leg from the caller, checking the combined amount before moving anything.
+        #[ink(message)]
+        pub fn transfer_batch(&mut self, recipients: Vec<(AccountId, Balance)>) -> Result<()> {
+			let owner = self._msg_sender();
+			self.ensure_migrated()?;
+			self.ensure_not_paused()?;
+			let total = recipients
+				.iter()
+				.try_fold(0 as Balance, |total, (_, amount)| total.checked_add(*amount))
+				.ok_or(Error::ArithmeticOverflow)?;
+			if self.balance_of(owner) < total {
+				return Err(Error::InsufficientBalance);
+			}
+
+			for (to, amount) in recipients {
+				self._transfer_or_queue(&owner, &to, amount)?;
+			}
+
+			Ok(())
+        }
+
+        /// Allows `spender` to move up to `amount` of the caller's tokens, replacing any previous allowance.
+        #[ink(message)]
+        pub fn approve(&mut self, spender: AccountId, amount: Balance) -> Result<()> {
+			Self::ensure_not_zero(&spender)?;
+			let owner = self._msg_sender();
+			self._v2._allowance_expiries.remove((owner, spender));
+			self._approve(&owner, &spender, amount);
+
+			Ok(())
+        }
+
+        /// Lets `spender` `transfer_from` up to `amount_per_period` of the caller's tokens every `period_blocks`
+        /// blocks, starting now, for subscriptions and payroll. A zero amount revokes it.
+        #[ink(message)]
+        pub fn approve_recurring(&mut self, spender: AccountId, amount_per_period: Balance, period_blocks: BlockNumber) -> Result<()> {
+			Self::ensure_not_zero(&spender)?;
+			let owner = self._msg_sender();
+			if amount_per_period == 0 {
+				self._v2._recurring_allowances.remove((owner, spender));
+			} else {
+				if period_blocks == 0 {
+					return Err(Error::InvalidRecurringAllowance);
+				}
+				let period_start = self.env().block_number();
+				self._v2._recurring_allowances.insert((owner, spender), &RecurringAllowance {
+					amount_per_period,
+					period_blocks,
+					period_start,
+					spent: 0,
+				});
+			}
+
+			Self::env().emit_event(RecurringApproval {
+				owner,
+				spender,
+				amount_per_period,
+				period_blocks,
+			});
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn recurring_allowance(&self, owner: AccountId, spender: AccountId) -> Option<RecurringAllowance> {
+            self._v2._recurring_allowances.get((owner, spender))
+        }
+
+        /// What `spender` can still pull from `owner` through its recurring allowance this period.
+        #[ink(message)]
+        pub fn recurring_available(&self, owner: AccountId, spender: AccountId) -> Balance {
+			let Some(mut recurring) = self._v2._recurring_allowances.get((owner, spender)) else {
+				return 0;
+			};
+			recurring.reset_at(self.env().block_number());
+
+			recurring.amount_per_period - recurring.spent
+        }
+
+        /// Like `approve`, but the allowance reads as zero from `expires_at` on.
+        #[ink(message)]
+        pub fn approve_with_expiry(&mut self, spender: AccountId, amount: Balance, expires_at: Timestamp) -> Result<()> {
+			Self::ensure_not_zero(&spender)?;
+			let owner = self._msg_sender();
+			if expires_at <= self.env().block_timestamp() {
+				return Err(Error::InvalidExpiry);
+			}
+			self._v2._allowance_expiries.insert((owner, spender), &expires_at);
+			self._approve(&owner, &spender, amount);
+
+			Ok(())
+        }
+
+        /// Raises the caller's allowance for `spender` by `delta`, saturating at `Balance::MAX`.
+        #[ink(message)]
+        pub fn increase_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+			Self::ensure_not_zero(&spender)?;
+			let owner = self._msg_sender();
+			let allowance = self.allowance(owner, spender);
+			// An expired allowance counts as zero, so the increase starts a fresh one without expiry.
+			if self._is_allowance_expired(&owner, &spender) {
+				self._v2._allowance_expiries.remove((owner, spender));
+			}
+			self._approve(&owner, &spender, allowance.saturating_add(delta));
+
+			Ok(())
+        }
+
+        /// Lowers the caller's allowance for `spender` by `delta`, failing rather than going below zero.
+        #[ink(message)]
+        pub fn decrease_allowance(&mut self, spender: AccountId, delta: Balance) -> Result<()> {
+			let owner = self._msg_sender();
+			let allowance = self.allowance(owner, spender);
+			if allowance < delta {
+				return Err(Error::InsufficientAllowance);
+			}
+			self._approve(&owner, &spender, allowance - delta);
+
+			Ok(())
+        }
+
+        /// Sets `owner`'s allowance for `spender` to `value` from an ecdsa signature by `owner`, so the
+        /// owner needs no native balance. The signature covers the SCALE encoding of
+        /// `(domain_separator(), PERMIT_TYPE, owner, spender, value, nonce_of(owner), deadline)`, hashed with Blake2x256.
+        #[ink(message)]
+        pub fn permit(
+            &mut self,
+            owner: AccountId,
+            spender: AccountId,
+            value: Balance,
+            deadline: Timestamp,
+            signature: [u8; 65],
+        ) -> Result<()> {
+			Self::ensure_not_zero(&spender)?;
+			if self.env().block_timestamp() > deadline {
+				return Err(Error::PermitExpired);
+			}
+			let nonce = self.nonce_of(owner);
+			let mut message = [0u8; 32];
+			ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(
+				&(self.domain_separator(), PERMIT_TYPE, owner, spender, value, nonce, deadline),
+				&mut message,
+			);
+			if self._recover_signer(&message, &signature)? != owner {
+				return Err(Error::InvalidSignature);
+			}
+			self._v2._nonces.insert(owner, &(nonce + 1));
+			self._v2._allowance_expiries.remove((owner, spender));
+			self._approve(&owner, &spender, value);
+
+			Ok(())
+        }
+
+        /// The nonce `account` must sign into its next `permit`.
+        #[ink(message)]
+        pub fn nonce_of(&self, account: AccountId) -> u64 {
+            self._v2._nonces.get(account).unwrap_or(0)
+        }
+
+        /// Moves `value` from `from` to `to` on an ecdsa signature by `from`, letting a relayer pay for the call.
+        /// The signature covers the SCALE encoding of `(domain_separator(), TRANSFER_WITH_AUTHORIZATION_TYPE, from, to,
+        /// value, valid_after, valid_before, nonce)`, hashed with Blake2x256. `nonce` is any unused value the signer picks.
+        #[ink(message)]
+        #[allow(clippy::too_many_arguments)]
+        pub fn transfer_with_authorization(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            valid_after: Timestamp,
+            valid_before: Timestamp,
+            nonce: Hash,
+            signature: [u8; 65],
+        ) -> Result<()> {
+			let now = self.env().block_timestamp();
+			if now <= valid_after {
+				return Err(Error::AuthorizationNotYetValid);
+			}
+			if now >= valid_before {
+				return Err(Error::AuthorizationExpired);
+			}
+			if self.authorization_state(from, nonce) {
+				return Err(Error::AuthorizationUsed);
+			}
+			let mut message = [0u8; 32];
+			ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(
+				&(self.domain_separator(), TRANSFER_WITH_AUTHORIZATION_TYPE, from, to, value, valid_after, valid_before, nonce),
+				&mut message,
+			);
+			if self._recover_signer(&message, &signature)? != from {
+				return Err(Error::InvalidSignature);
+			}
+			self._transfer_or_queue(&from, &to, value)?;
+			self._v2._authorization_states.insert((from, nonce), &true);
+
+			Ok(())
+        }
+
+        /// Whether `authorizer` already used `nonce` for a transfer authorization.
+        #[ink(message)]
+        pub fn authorization_state(&self, authorizer: AccountId, nonce: Hash) -> bool {
+            self._v2._authorization_states.get((authorizer, nonce)).unwrap_or(false)
+        }
+
+        /// Binds signed payloads to this contract instance.
+        #[ink(message)]
+        pub fn domain_separator(&self) -> Hash {
+			let mut separator = [0u8; 32];
+			ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&(b"ink-erc20", self.env().account_id()), &mut separator);
+			Hash::from(separator)
+        }
+
+        /// Moves `amount` from `from` to `to` on behalf of `from`, consuming the caller's allowance.
+        #[ink(message)]
+        pub fn transfer_from(&mut self, from: AccountId, to: AccountId, amount: Balance) -> Result<()> {
+			let spender = self._msg_sender();
+			if self.is_operator(from, spender) {
+				self._transfer_or_queue(&from, &to, amount)?;
+				return Ok(());
+			}
+			// The recurring allowance is used first when it covers the amount, leaving the plain one intact.
+			if let Some(mut recurring) = self._v2._recurring_allowances.get((from, spender)) {
+				recurring.reset_at(self.env().block_number());
+				if amount <= recurring.amount_per_period - recurring.spent {
+					self._transfer_or_queue(&from, &to, amount)?;
+					recurring.spent += amount;
+					self._v2._recurring_allowances.insert((from, spender), &recurring);
+
+					return Ok(());
+				}
+			}
+			if self._is_allowance_expired(&from, &spender) {
+				return Err(Error::AllowanceExpired);
+			}
+			let allowance = self.allowance(from, spender);
+			if allowance < amount {
+				return Err(Error::InsufficientAllowance);
+			}
+			self._transfer_or_queue(&from, &to, amount)?;
+			self._approve(&from, &spender, allowance - amount);
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn delayed_transfer(&self, id: u64) -> Option<DelayedTransfer> {
+            self._delayed_transfers.get(id)
+        }
+
+        #[ink(message)]
+        pub fn execute_delayed_transfer(&mut self, id: u64) -> Result<()> {
+			let delayed = self._delayed_transfers.get(id).ok_or(Error::DelayedTransferNotFound)?;
+			if self.env().block_timestamp() < delayed.executable_at {
+				return Err(Error::DelayedTransferNotReady);
+			}
+			self._transfer(&self.env().account_id(), &delayed.to, delayed.amount)?;
+			self._delayed_transfers.remove(id);
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn cancel_delayed_transfer(&mut self, id: u64) -> Result<()> {
+			let delayed = self._delayed_transfers.get(id).ok_or(Error::DelayedTransferNotFound)?;
+			if delayed.from != self._msg_sender() {
+				return Err(Error::NotDelayedTransferSender);
+			}
+			self._transfer(&self.env().account_id(), &delayed.from, delayed.amount)?;
+			self._delayed_transfers.remove(id);
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn active_limits(&self) -> ActiveLimits {
+			let large_transfer_enabled = self._large_transfer_threshold != 0;
+
+			ActiveLimits {
+				max_mint_per_tx: Some(self._max_mint_per_tx).filter(|max| *max != 0),
+				max_tx_amount: Some(self._v2._max_tx_amount).filter(|max| *max != 0),
+				max_wallet_balance: Some(self._v2._max_wallet_balance).filter(|max| *max != 0),
+				cooldown_blocks: Some(self._v2._cooldown_blocks).filter(|blocks| *blocks != 0),
+				mint_limit_per_period: Some(self._v2._mint_limit_per_period).filter(|limit| *limit != 0),
+				mint_period_length: Some(self._v2._mint_period_length).filter(|_| self._v2._mint_limit_per_period != 0),
+				large_transfer_threshold: Some(self._large_transfer_threshold).filter(|_| large_transfer_enabled),
+				large_transfer_delay: Some(self._large_transfer_delay).filter(|_| large_transfer_enabled),
+			}
+        }
+
+        #[ink(message)]
+        pub fn large_transfer_threshold(&self) -> Balance {
+            self._large_transfer_threshold
+        }
+
+        #[ink(message)]
+        pub fn large_transfer_delay(&self) -> Timestamp {
+            self._large_transfer_delay
+        }
+
+        #[ink(message)]
+        pub fn set_large_transfer_limit(&mut self, threshold: Balance, delay: Timestamp) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._large_transfer_threshold = threshold;
+			self._large_transfer_delay = delay;
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn mint(&mut self, amount: Balance) -> Result<()> {
+			let owner = self._msg_sender();
+			self.only_role(MINTER_ROLE)?;
+			self.ensure_not_timelocked()?;
+			self._mint(&owner, amount)
+        }
+
+        /// Mints `amount` straight into `account`.
+        #[ink(message)]
+        pub fn mint_to(&mut self, account: AccountId, amount: Balance) -> Result<()> {
+			self.only_role(MINTER_ROLE)?;
+			self.ensure_not_timelocked()?;
+			self._mint(&account, amount)
+        }
+
+        #[ink(message)]
+        pub fn burn(&mut self, amount: Balance) -> Result<()> {
+			let owner = self._msg_sender();
+			self.only_role(BURNER_ROLE)?;
+			self._burn(&owner, amount)
+        }
+
+        /// Burns `amount` from `account`, spending the caller's allowance.
+        #[ink(message)]
+        pub fn burn_from(&mut self, account: AccountId, amount: Balance) -> Result<()> {
+			let spender = self._msg_sender();
+			self.only_role(BURNER_ROLE)?;
+			let allowance = self.allowance(account, spender);
+			if allowance < amount {
+				return Err(Error::InsufficientAllowance);
+			}
+			self._burn(&account, amount)?;
+			self._approve(&account, &spender, allowance - amount);
+
+			Ok(())
+        }
+
+        /// Burns `amounts[i]` from `accounts[i]` like `burn_from`, all or nothing.
+        #[ink(message)]
+        pub fn burn_from_batch(&mut self, accounts: Vec<AccountId>, amounts: Vec<Balance>) -> Result<()> {
+			let spender = self._msg_sender();
+			self.only_role(BURNER_ROLE)?;
+			if accounts.len() != amounts.len() {
+				return Err(Error::BatchLengthMismatch);
+			}
+			self.ensure_migrated()?;
+			self.ensure_not_paused()?;
+			// Check each account against its total, in case it appears more than once.
+			let mut totals: Vec<(AccountId, Balance)> = Vec::new();
+			for (account, amount) in accounts.iter().zip(&amounts) {
+				match totals.iter_mut().find(|(seen, _)| seen == account) {
+					Some((_, total)) => *total = total.checked_add(*amount).ok_or(Error::ArithmeticOverflow)?,
+					None => totals.push((*account, *amount)),
+				}
+			}
+			for (account, total) in totals {
+				if self.allowance(account, spender) < total {
+					return Err(Error::InsufficientAllowance);
+				}
+				if self.balance_of(account) < total {
+					return Err(Error::InsufficientBalance);
+				}
+			}
+
+			for (account, amount) in accounts.into_iter().zip(amounts) {
+				self.burn_from(account, amount)?;
+			}
+
+			Ok(())
+        }
+
+        /// Layout version storage was last written or migrated with.
+        #[ink(message)]
+        pub fn storage_version(&self) -> u32 {
+            self._version
+        }
+
+        /// Swaps the contract code for the code uploaded under `code_hash`, keeping storage. If the new code
+        /// has a higher `STORAGE_VERSION`, balance changes are refused until the owner calls `migrate`.
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<()> {
+			self.only_allowed_caller()?;
+			self.ensure_not_timelocked()?;
+			self._upgrade(code_hash)
+        }
+
+        /// Removes the contract and sends its native balance to the owner. Refused while anything is held in
+        /// custody, so nobody's escrow, vesting, stream, stake or wrapped backing is destroyed with it.
+        #[ink(message)]
+        pub fn terminate(&mut self) -> Result<()> {
+			self.only_allowed_caller()?;
+			let wrapped_backing = if self._v2._wrapped { self._total_supply } else { 0 };
+			let curve_reserve = self._v2._curve.as_ref().map_or(0, |curve| curve.reserve);
+			if self.balance_of(self.env().account_id()) > 0 || wrapped_backing > 0 || curve_reserve > 0 {
+				return Err(Error::OutstandingCommitments);
+			}
+
+			self.env().terminate_contract(self._msg_sender())
+        }
+
+        /// Whether storage was written by an older code version and `migrate` must run.
+        #[ink(message)]
+        pub fn needs_migration(&self) -> bool {
+            self._version < STORAGE_VERSION
+        }
+
+        /// Initializes fields added since the stored version; balance changes are refused until then.
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<()> {
+			self.only_allowed_caller()?;
+			if !self.needs_migration() {
+				return Err(Error::AlreadyMigrated);
+			}
+			// What `_init` sets for a new token; every other field added since 1 starts out at its default.
+			let owner = self._owner;
+			for role in OWNER_ROLES {
+				self._v2._roles.insert((role, owner), &true);
+			}
+			self._v2._limit_exempt.insert(owner, &true);
+			self._v2._trading_enabled = true;
+			self._v2._conditional_timeout = DEFAULT_CONDITIONAL_TIMEOUT;
+			self._version = STORAGE_VERSION;
+
+			Ok(())
+        }
+
+        /// Shares `amount` of the caller's tokens among all holders but the contract itself, pro rata to
+        /// their balances now. Holders collect their share with `withdraw_dividend`.
+        #[ink(message)]
+        pub fn distribute(&mut self, amount: Balance) -> Result<()> {
+			let from = self._msg_sender();
+			let contract = self.env().account_id();
+			let eligible_supply = self._total_supply
+				.saturating_sub(self.balance_of(contract))
+				.saturating_sub(if from == contract { 0 } else { amount });
+			if eligible_supply == 0 {
+				return Err(Error::NoDividendHolders);
+			}
+			self._transfer(&from, &contract, amount)?;
+			let per_share = amount.checked_mul(REWARD_PRECISION).ok_or(Error::ArithmeticOverflow)? / eligible_supply;
+			self._v2._magnified_dividend_per_share = self._v2._magnified_dividend_per_share
+				.checked_add(per_share)
+				.ok_or(Error::ArithmeticOverflow)?;
+
+			Self::env().emit_event(DividendsDistributed { from, amount });
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn withdrawable_dividend_of(&self, account: AccountId) -> Balance {
+			self._accumulative_dividend_of(&account)
+				.saturating_sub(self._v2._withdrawn_dividends.get(account).unwrap_or(0))
+        }
+
+        #[ink(message)]
+        pub fn withdrawn_dividend_of(&self, account: AccountId) -> Balance {
+            self._v2._withdrawn_dividends.get(account).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn withdraw_dividend(&mut self) -> Result<()> {
+			let account = self._msg_sender();
+			let amount = self.withdrawable_dividend_of(account);
+			if amount == 0 {
+				return Err(Error::NoDividend);
+			}
+			let withdrawn = self.withdrawn_dividend_of(account) + amount;
+			self._v2._withdrawn_dividends.insert(account, &withdrawn);
+			self._transfer(&self.env().account_id(), &account, amount)?;
+
+			Self::env().emit_event(DividendWithdrawn { account, amount });
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn escrow(&self, id: u64) -> Option<Escrow> {
+            self._v2._escrows.get(id)
+        }
+
+        /// Holds `amount` of the caller's tokens for `seller`, with `arbiter` settling disputes. Returns the escrow id.
+        #[ink(message)]
+        pub fn create_escrow(&mut self, seller: AccountId, arbiter: AccountId, amount: Balance) -> Result<u64> {
+			let buyer = self._msg_sender();
+			self._transfer(&buyer, &self.env().account_id(), amount)?;
+
+			let id = self._v2._next_escrow_id;
+			self._v2._next_escrow_id += 1;
+			self._v2._escrows.insert(id, &Escrow {
+				buyer,
+				seller,
+				arbiter,
+				amount,
+				disputed: false,
+			});
+
+			Self::env().emit_event(EscrowCreated {
+				id,
+				buyer,
+				seller,
+				arbiter,
+				amount,
+			});
+
+			Ok(id)
+        }
+
+        /// Pays escrow `id` to the seller; called by the buyer, or the arbiter.
+        #[ink(message)]
+        pub fn release_escrow(&mut self, id: u64) -> Result<()> {
+			let escrow = self._v2._escrows.get(id).ok_or(Error::EscrowNotFound)?;
+			let caller = self._msg_sender();
+			self._settle_escrow(id, &escrow, caller, escrow.buyer, escrow.seller)?;
+
+			Self::env().emit_event(EscrowReleased { id, by: caller });
+
+			Ok(())
+        }
+
+        /// Returns escrow `id` to the buyer; called by the seller, or the arbiter.
+        #[ink(message)]
+        pub fn refund_escrow(&mut self, id: u64) -> Result<()> {
+			let escrow = self._v2._escrows.get(id).ok_or(Error::EscrowNotFound)?;
+			let caller = self._msg_sender();
+			self._settle_escrow(id, &escrow, caller, escrow.seller, escrow.buyer)?;
+
+			Self::env().emit_event(EscrowRefunded { id, by: caller });
+
+			Ok(())
+        }
+
+        /// Flags escrow `id` as disputed by the buyer or seller, leaving it to the arbiter.
+        #[ink(message)]
+        pub fn dispute_escrow(&mut self, id: u64) -> Result<()> {
+			let mut escrow = self._v2._escrows.get(id).ok_or(Error::EscrowNotFound)?;
+			let caller = self._msg_sender();
+			if caller != escrow.buyer && caller != escrow.seller {
+				return Err(Error::NotEscrowParty);
+			}
+			if escrow.disputed {
+				return Err(Error::EscrowDisputed);
+			}
+			escrow.disputed = true;
+			self._v2._escrows.insert(id, &escrow);
+
+			Self::env().emit_event(EscrowDisputed { id, by: caller });
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn stream(&self, id: u64) -> Option<Stream> {
+            self._v2._streams.get(id)
+        }
+
+        /// What `who` would get out of stream `id` now: the recipient's streamed but unwithdrawn tokens,
+        /// or the sender's not yet streamed ones.
+        #[ink(message)]
+        pub fn balance_of_stream(&self, id: u64, who: AccountId) -> Balance {
+			let Some(stream) = self._v2._streams.get(id) else {
+				return 0;
+			};
+			let streamed = stream.streamed_at(self.env().block_timestamp());
+			if who == stream.recipient {
+				streamed - stream.withdrawn
+			} else if who == stream.sender {
+				stream.deposit - streamed
+			} else {
+				0
+			}
+        }
+
+        /// Locks `deposit` of the caller's tokens and streams them to `recipient` from `start` to `stop`. Returns the stream id.
+        #[ink(message)]
+        pub fn create_stream(&mut self, recipient: AccountId, deposit: Balance, start: Timestamp, stop: Timestamp) -> Result<u64> {
+			let sender = self._msg_sender();
+			if deposit == 0 || start >= stop || start < self.env().block_timestamp() {
+				return Err(Error::InvalidStream);
+			}
+			self._transfer(&sender, &self.env().account_id(), deposit)?;
+
+			let id = self._v2._next_stream_id;
+			self._v2._next_stream_id += 1;
+			self._v2._streams.insert(id, &Stream {
+				sender,
+				recipient,
+				deposit,
+				start,
+				stop,
+				withdrawn: 0,
+			});
+
+			Self::env().emit_event(StreamCreated {
+				id,
+				sender,
+				recipient,
+				deposit,
+				start,
+				stop,
+			});
+
+			Ok(id)
+        }
+
+        /// Pays `amount` of what has streamed so far to the recipient, who must be the caller.
+        #[ink(message)]
+        pub fn withdraw_from_stream(&mut self, id: u64, amount: Balance) -> Result<()> {
+			let mut stream = self._v2._streams.get(id).ok_or(Error::StreamNotFound)?;
+			if stream.recipient != self._msg_sender() {
+				return Err(Error::NotStreamParty);
+			}
+			if amount > self.balance_of_stream(id, stream.recipient) {
+				return Err(Error::InsufficientStreamBalance);
+			}
+			self._transfer(&self.env().account_id(), &stream.recipient, amount)?;
+			stream.withdrawn += amount;
+			if stream.withdrawn == stream.deposit {
+				self._v2._streams.remove(id);
+			} else {
+				self._v2._streams.insert(id, &stream);
+			}
+
+			Self::env().emit_event(WithdrawFromStream {
+				id,
+				recipient: stream.recipient,
+				amount,
+			});
+
+			Ok(())
+        }
+
+        /// Ends stream `id`, paying the recipient what has streamed and refunding the rest to the sender.
+        #[ink(message)]
+        pub fn cancel_stream(&mut self, id: u64) -> Result<()> {
+			let stream = self._v2._streams.get(id).ok_or(Error::StreamNotFound)?;
+			let caller = self._msg_sender();
+			if caller != stream.sender && caller != stream.recipient {
+				return Err(Error::NotStreamParty);
+			}
+			let recipient_balance = self.balance_of_stream(id, stream.recipient);
+			let sender_balance = self.balance_of_stream(id, stream.sender);
+			let contract = self.env().account_id();
+			if recipient_balance > 0 {
+				self._transfer(&contract, &stream.recipient, recipient_balance)?;
+			}
+			if sender_balance > 0 {
+				self._transfer(&contract, &stream.sender, sender_balance)?;
+			}
+			self._v2._streams.remove(id);
+
+			Self::env().emit_event(StreamCancelled {
+				id,
+				sender_balance,
+				recipient_balance,
+			});
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn hashlocked_swap(&self, id: u64) -> Option<HashLockedSwap> {
+            self._v2._hashlocked_swaps.get(id)
+        }
+
+        /// Escrows `amount` of the caller's tokens for `recipient` until `timelock`, claimable with the
+        /// Sha2x256 preimage of `hashlock`. Returns the swap id.
+        #[ink(message)]
+        pub fn create_swap(&mut self, recipient: AccountId, amount: Balance, hashlock: Hash, timelock: Timestamp) -> Result<u64> {
+			let sender = self._msg_sender();
+			if timelock <= self.env().block_timestamp() {
+				return Err(Error::InvalidTimelock);
+			}
+			self._transfer(&sender, &self.env().account_id(), amount)?;
+
+			let id = self._v2._next_hashlocked_swap_id;
+			self._v2._next_hashlocked_swap_id += 1;
+			self._v2._hashlocked_swaps.insert(id, &HashLockedSwap {
+				sender,
+				recipient,
+				amount,
+				hashlock,
+				timelock,
+			});
+
+			Self::env().emit_event(SwapCreated {
+				id,
+				sender,
+				recipient,
+				amount,
+				hashlock,
+				timelock,
+			});
+
+			Ok(id)
+        }
+
+        /// Pays swap `id` to its recipient; anyone holding the preimage may submit it before the timelock.
+        #[ink(message)]
+        pub fn claim_swap(&mut self, id: u64, preimage: Vec<u8>) -> Result<()> {
+			let swap = self._v2._hashlocked_swaps.get(id).ok_or(Error::SwapNotFound)?;
+			if self.env().block_timestamp() >= swap.timelock {
+				return Err(Error::SwapExpired);
+			}
+			let mut hash = [0u8; 32];
+			ink_env::hash_bytes::<ink_env::hash::Sha2x256>(&preimage, &mut hash);
+			if Hash::from(hash) != swap.hashlock {
+				return Err(Error::InvalidPreimage);
+			}
+			self._transfer(&self.env().account_id(), &swap.recipient, swap.amount)?;
+			self._v2._hashlocked_swaps.remove(id);
+
+			Self::env().emit_event(SwapClaimed { id, preimage });
+
+			Ok(())
+        }
+
+        /// Returns swap `id` to its sender once the timelock passed unclaimed.
+        #[ink(message)]
+        pub fn refund_swap(&mut self, id: u64) -> Result<()> {
+			let swap = self._v2._hashlocked_swaps.get(id).ok_or(Error::SwapNotFound)?;
+			if self.env().block_timestamp() < swap.timelock {
+				return Err(Error::SwapNotExpired);
+			}
+			self._transfer(&self.env().account_id(), &swap.sender, swap.amount)?;
+			self._v2._hashlocked_swaps.remove(id);
+
+			Self::env().emit_event(SwapRefunded { id });
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn swap_offer(&self, id: u64) -> Option<SwapOffer> {
+            self._swap_offers.get(id)
+        }
+
+        /// Escrows `give_amount` of the caller's tokens until `counterparty` pays
+        /// `want_amount` of `want_token`. Returns the offer id.
+        #[ink(message)]
+        pub fn create_swap_offer(
+            &mut self,
+            counterparty: AccountId,
+            give_amount: Balance,
+            want_token: AccountId,
+            want_amount: Balance,
+        ) -> Result<u64> {
+			let maker = self._msg_sender();
+			self._transfer(&maker, &self.env().account_id(), give_amount)?;
+
+			let id = self._next_swap_offer_id;
+			self._next_swap_offer_id += 1;
+			self._swap_offers.insert(id, &SwapOffer {
+				maker,
+				counterparty,
+				give_amount,
+				want_token,
+				want_amount,
+			});
+
+			Ok(id)
+        }
+
+        /// Pays the maker through the PSP22 `transfer_from` of `want_token` and releases the escrow to the counterparty.
+        #[ink(message)]
+        pub fn accept_swap_offer(&mut self, id: u64) -> Result<()> {
+			let offer = self._swap_offers.get(id).ok_or(Error::SwapOfferNotFound)?;
+			if offer.counterparty != self._msg_sender() {
+				return Err(Error::NotSwapCounterparty);
+			}
+			// Nothing may fail after the payment went through.
+			self.ensure_migrated()?;
+			self.ensure_not_paused()?;
+
+			let paid = super::psp22_calls::transfer_from(
+				offer.want_token,
+				offer.counterparty,
+				offer.maker,
+				offer.want_amount,
+				Vec::new(),
+			)
+			.fire();
+			if paid != Ok(Ok(())) {
+				return Err(Error::SwapPaymentFailed);
+			}
+
+			self._swap_offers.remove(id);
+			self._transfer(&self.env().account_id(), &offer.counterparty, offer.give_amount)
+        }
+
+        #[ink(message)]
+        pub fn cancel_swap_offer(&mut self, id: u64) -> Result<()> {
+			let offer = self._swap_offers.get(id).ok_or(Error::SwapOfferNotFound)?;
+			if offer.maker != self._msg_sender() {
+				return Err(Error::NotSwapMaker);
+			}
+			self._transfer(&self.env().account_id(), &offer.maker, offer.give_amount)?;
+			self._swap_offers.remove(id);
+
+			Ok(())
+        }
+
+        /// Burns `amount` of the caller's tokens, or with `redirect` moves them to the
+        /// burn redirect pool instead, leaving total supply unchanged.
+        #[ink(message)]
+        pub fn burn_with_option(&mut self, amount: Balance, redirect: bool) -> Result<()> {
+			let owner = self._msg_sender();
+			self.only_role(BURNER_ROLE)?;
+			if redirect {
+				let pool = self._v2._burn_redirect.ok_or(Error::NoBurnRedirect)?;
+				self._transfer(&owner, &pool, amount)
+			} else {
+				self._burn(&owner, amount)
+			}
+        }
+
+        #[ink(message)]
+        pub fn burn_redirect(&self) -> Option<AccountId> {
+            self._v2._burn_redirect
+        }
+
+        #[ink(message)]
+        pub fn set_burn_redirect(&mut self, pool: Option<AccountId>) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._v2._burn_redirect = pool;
+
+			Ok(())
+        }
+
+        /// Builds a Merkle tree over `(account, balance)` leaves of `ordered_holders`, which must be every
+        /// tracked holder, sorted by account without duplicates, and stores its root.
+        #[ink(message)]
+        pub fn compute_balance_root(&mut self, ordered_holders: Vec<AccountId>) -> Result<Hash> {
+			self.only_allowed_caller()?;
+			if ordered_holders.is_empty() {
+				return Err(Error::NoHolders);
+			}
+			if !ordered_holders.windows(2).all(|pair| pair[0] < pair[1]) {
+				return Err(Error::HoldersNotOrdered);
+			}
+			// Sorted and distinct, so the same length and membership make it the whole holder set.
+			if ordered_holders.len() != self.holder_count() as usize
+				|| !ordered_holders.iter().all(|holder| self._v2._holder_index.contains(holder)) {
+				return Err(Error::HoldersIncomplete);
+			}
+
+			let mut level = ordered_holders.iter().map(|holder| {
+				match self.balance_of(*holder) {
+					0 => Err(Error::HolderWithoutBalance),
+					balance => Ok(Self::_balance_leaf(holder, balance)),
+				}
+			}).collect::<Result<Vec<_>>>()?;
+			while level.len() > 1 {
+				level = level.chunks(2).map(|pair| match pair {
+					[left, right] => Self::_hash_pair(left, right),
+					[single] => *single,
+					_ => unreachable!(),
+				}).collect();
+			}
+
+			self._v2._balance_root = level[0];
+			self._v2._balance_root_at = self.env().block_timestamp();
+
+			Ok(self._v2._balance_root)
+        }
+
+        #[ink(message)]
+        pub fn balance_root(&self) -> (Hash, Timestamp) {
+            (self._v2._balance_root, self._v2._balance_root_at)
+        }
+
+        /// Checks `proof` for the `(account, balance)` leaf against the stored balance root.
+        #[ink(message)]
+        pub fn verify_balance_proof(&self, account: AccountId, balance: Balance, proof: Vec<Hash>) -> bool {
+			let root = proof.iter().fold(Self::_balance_leaf(&account, balance), |node, sibling| {
+				Self::_hash_pair(&node, sibling)
+			});
+
+			root == self._v2._balance_root
+        }
+
+        /// Burns the caller's tokens so relayers can release them to `dest_account` on `dest_chain`.
+        #[ink(message)]
+        pub fn bridge_lock(&mut self, amount: Balance, dest_chain: u32, dest_account: Vec<u8>) -> Result<()> {
+			let from = self._msg_sender();
+			self._burn(&from, amount)?;
+
+			Self::env().emit_event(BridgeLocked {
+				from,
+				amount,
+				dest_chain,
+				dest_account,
+			});
+
+			Ok(())
+        }
+
+        /// Mints tokens locked on `src_chain`; each `(src_chain, nonce)` unlocks once.
+        #[ink(message)]
+        pub fn bridge_unlock(&mut self, to: AccountId, amount: Balance, src_chain: u32, nonce: u64) -> Result<()> {
+			let caller = self._msg_sender();
+			if self._v2._bridge_relayer != Some(caller) {
+				self.only_allowed_caller()?;
+			}
+			self.ensure_not_timelocked()?;
+			if self._v2._bridge_nonces.get((src_chain, nonce)).unwrap_or(false) {
+				return Err(Error::BridgeNonceUsed);
+			}
+			self._mint(&to, amount)?;
+			self._v2._bridge_nonces.insert((src_chain, nonce), &true);
+
+			Self::env().emit_event(BridgeUnlocked {
+				to,
+				amount,
+				src_chain,
+				nonce,
+			});
+
+			Ok(())
+        }
+
+        /// Mints `amount` to `to` for a transfer made in `src_tx` on `src_chain`; each pair mints once.
+        /// Unlike the single relayer of `bridge_unlock`, any number of `BRIDGE_ROLE` holders can do this,
+        /// each within its own mint cap.
+        #[ink(message)]
+        pub fn bridge_mint(&mut self, to: AccountId, amount: Balance, src_chain: u32, src_tx: Hash) -> Result<()> {
+			let bridge = self._msg_sender();
+			self.only_role(BRIDGE_ROLE)?;
+			self.ensure_not_timelocked()?;
+			if self._v2._bridge_src_txs.get((src_chain, src_tx)).unwrap_or(false) {
+				return Err(Error::BridgeTxUsed);
+			}
+			let minted = self.bridge_minted(bridge).checked_add(amount).ok_or(Error::ArithmeticOverflow)?;
+			if self._v2._bridge_mint_caps.get(bridge).is_some_and(|cap| minted > cap) {
+				return Err(Error::BridgeCapExceeded);
+			}
+			self._mint(&to, amount)?;
+			self._v2._bridge_src_txs.insert((src_chain, src_tx), &true);
+			self._v2._bridge_minted.insert(bridge, &minted);
+
+			Self::env().emit_event(BridgeMinted {
+				bridge,
+				to,
+				amount,
+				src_chain,
+				src_tx,
+			});
+
+			Ok(())
+        }
+
+        /// Burns `amount` of `from`'s tokens, spending its allowance to the bridge unless it is the bridge
+        /// itself, so relayers can release them to `dst_address` on `dst_chain`.
+        #[ink(message)]
+        pub fn bridge_burn(&mut self, from: AccountId, amount: Balance, dst_chain: u32, dst_address: Vec<u8>) -> Result<()> {
+			let bridge = self._msg_sender();
+			self.only_role(BRIDGE_ROLE)?;
+			let allowance = self.allowance(from, bridge);
+			if from != bridge && allowance < amount {
+				return Err(Error::InsufficientAllowance);
+			}
+			self._burn(&from, amount)?;
+			if from != bridge {
+				self._approve(&from, &bridge, allowance - amount);
+			}
+			let minted = self.bridge_minted(bridge).saturating_sub(amount);
+			self._v2._bridge_minted.insert(bridge, &minted);
+
+			Self::env().emit_event(BridgeBurned {
+				bridge,
+				from,
+				amount,
+				dst_chain,
+				dst_address,
+			});
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn is_bridge_tx_used(&self, src_chain: u32, src_tx: Hash) -> bool {
+            self._v2._bridge_src_txs.get((src_chain, src_tx)).unwrap_or(false)
+        }
+
+        /// What `bridge` minted and has not burned back.
+        #[ink(message)]
+        pub fn bridge_minted(&self, bridge: AccountId) -> Balance {
+            self._v2._bridge_minted.get(bridge).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        pub fn bridge_mint_cap(&self, bridge: AccountId) -> Option<Balance> {
+            self._v2._bridge_mint_caps.get(bridge)
+        }
+
+        #[ink(message)]
+        pub fn set_bridge_mint_cap(&mut self, bridge: AccountId, cap: Option<Balance>) -> Result<()> {
+			self.only_allowed_caller()?;
+			match cap {
+				Some(cap) => self._v2._bridge_mint_caps.insert(bridge, &cap),
+				None => self._v2._bridge_mint_caps.remove(bridge),
+			}
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn bridge_relayer(&self) -> Option<AccountId> {
+            self._v2._bridge_relayer
+        }
+
+        #[ink(message)]
+        pub fn set_bridge_relayer(&mut self, relayer: Option<AccountId>) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._v2._bridge_relayer = relayer;
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn conditional_transfer(&self, from: AccountId, condition_id: Hash) -> Option<ConditionalTransfer> {
+            self._v2._conditional_transfers.get((from, condition_id))
+        }
+
+        /// Escrows `amount` for `to` until `resolver.is_met(condition_id)` is resolved.
+        #[ink(message)]
+        pub fn create_conditional_transfer(
+            &mut self,
+            to: AccountId,
+            amount: Balance,
+            resolver: AccountId,
+            condition_id: Hash,
+        ) -> Result<()> {
+			let from = self._msg_sender();
+			if self._v2._conditional_transfers.contains((from, condition_id)) {
+				return Err(Error::ConditionInUse);
+			}
+			self._transfer(&from, &self.env().account_id(), amount)?;
+
+			let expires_at = self.env().block_timestamp() + self._v2._conditional_timeout;
+			self._v2._conditional_transfers.insert((from, condition_id), &ConditionalTransfer {
+				from,
+				to,
+				amount,
+				resolver,
+				expires_at,
+			});
+
+			Ok(())
+        }
+
+        /// Asks the resolver about the condition of `from`'s conditional transfer and releases the escrow
+        /// to the recipient if met, or back to the sender otherwise.
+        #[ink(message)]
+        pub fn resolve_conditional(&mut self, from: AccountId, condition_id: Hash) -> Result<()> {
+			let conditional = self._v2._conditional_transfers.get((from, condition_id))
+				.ok_or(Error::ConditionalTransferNotFound)?;
+			self.ensure_migrated()?;
+			self.ensure_not_paused()?;
+
+			let receiver = if Self::_is_condition_met(conditional.resolver, condition_id)? {
+				conditional.to
+			} else {
+				conditional.from
+			};
+			self._v2._conditional_transfers.remove((from, condition_id));
+			self._transfer(&self.env().account_id(), &receiver, conditional.amount)
+        }
+
+        /// Returns the escrow of the caller's conditional transfer once it has expired unresolved.
+        #[ink(message)]
+        pub fn reclaim_conditional(&mut self, condition_id: Hash) -> Result<()> {
+			let from = self._msg_sender();
+			let conditional = self._v2._conditional_transfers.get((from, condition_id))
+				.ok_or(Error::ConditionalTransferNotFound)?;
+			if self.env().block_timestamp() < conditional.expires_at {
+				return Err(Error::ConditionalTransferNotExpired);
+			}
+			self._transfer(&self.env().account_id(), &from, conditional.amount)?;
+			self._v2._conditional_transfers.remove((from, condition_id));
+
+			Ok(())
+        }
+
+        #[ink(message)]
+        pub fn conditional_timeout(&self) -> Timestamp {
+            self._v2._conditional_timeout
+        }
+
+        #[ink(message)]
+        pub fn set_conditional_timeout(&mut self, timeout: Timestamp) -> Result<()> {
+			self.only_allowed_caller()?;
+			self._v2._conditional_timeout = timeout;
+
+			Ok(())
+        }
+
+		/// Transfers directly, or escrows and queues the transfer when above the large transfer threshold.
+		/// Returns the ticket id if the transfer was queued.
+		fn _transfer_or_queue(&mut self, from: &AccountId, to: &AccountId, amount: Balance) -> Result<Option<u64>> {
+			if self._large_transfer_threshold == 0 || amount <= self._large_transfer_threshold {
+				self._transfer(from, to, amount)?;
+				return Ok(None);
+			}
+
+			// The escrow legs involve the contract and skip the limits, so check them against the real parties here.
+			self._check_transfer(from, to, amount)?;
+			// The escrow legs are never charged, so fee and burn are taken up front; the queue holds and later
+			// delivers only what is left.
+			let amount = if cfg!(feature = "fees") {
+				self._apply_transfer_charges(from, to, amount)?
+			} else {
+				amount
+			};
+			self._transfer(from, &self.env().account_id(), amount)?;
+			let id = self._next_delayed_transfer_id;
+			self._next_delayed_transfer_id += 1;
+			let executable_at = self.env().block_timestamp() + self._large_transfer_delay;
+			self._delayed_transfers.insert(id, &DelayedTransfer {
+				from: *from,
+				to: *to,
+				amount,
+				executable_at,
+			});
+
+			Self::env().emit_event(DelayedTransferQueued {
+				id,
+				from: *from,
+				to: *to,
+				value: amount,
+				executable_at,
+			});
+
+			Ok(Some(id))
+		}
+
+		/// A user-initiated transfer: restrictions, launch guard and limits apply on top of the hooks every
+		/// balance change runs through.
+		fn _transfer(&mut self, from: &AccountId, to: &AccountId, amount: Balance) -> Result<()> {
+			self._check_transfer(from, to, amount)?;
+			self._move_balance(from, to, amount)?;
+			if self._v2._cooldown_blocks != 0 && self._is_launch_guarded(from) {
+				let block = self.env().block_number();
+				self._v2._last_transfer_block.insert(from, &block);
+			}
+
+			Ok(())
+		}
+
+		/// Refuses a user-initiated transfer that the restrictions, launch guard or limits forbid.
+		fn _check_transfer(&self, from: &AccountId, to: &AccountId, amount: Balance) -> Result<()> {
+			// The hooks check these too, but ahead of the restrictions errors match `detect_transfer_restriction`.
+			self.ensure_migrated()?;
+			self.ensure_not_paused()?;
+			if self.is_frozen(*from) || self.is_frozen(*to) {
+				return Err(Error::AccountFrozen);
+			}
+			if !self._allowlist_permits(from, to) {
+				return Err(Error::NotAllowlisted);
+			}
+			if let Some(rules) = self._v2._restriction_rules {
+				let code = Self::_rules_restriction(rules, from, to, amount);
+				if code != RESTRICTION_SUCCESS {
+					return Err(Error::TransferRestricted(code));
+				}
+			}
+			if self.balance_of(*from) < amount {
+				return Err(Error::InsufficientBalance);
+			}
+			self._check_launch_guard(from)?;
+			self._check_transfer_limits(from, to, amount)
+		}
+
+		/// Whether trading and cooldown rules apply to transfers sent by `from`.
+		fn _is_launch_guarded(&self, from: &AccountId) -> bool {
+			*from != self.env().account_id() && !self.is_limit_exempt(*from)
+		}
+
+		fn _check_launch_guard(&self, from: &AccountId) -> Result<()> {
+			if !self._is_launch_guarded(from) {
+				return Ok(());
+			}
+			if !self._v2._trading_enabled {
+				return Err(Error::TradingNotEnabled);
+			}
+			if self._v2._cooldown_blocks != 0 {
+				if let Some(last) = self._v2._last_transfer_block.get(from) {
+					if self.env().block_number() < last.saturating_add(self._v2._cooldown_blocks) {
+						return Err(Error::CooldownActive);
+					}
+				}
+			}
+
+			Ok(())
+		}
+
+		/// Enforces the max transaction and max wallet limits. Moves in and out of the contract's own escrow are never limited.
+		fn _check_transfer_limits(&self, from: &AccountId, to: &AccountId, amount: Balance) -> Result<()> {
+			let contract = self.env().account_id();
+			if *from == contract || *to == contract {
+				return Ok(());
+			}
+			let from_exempt = self.is_limit_exempt(*from);
+			let to_exempt = self.is_limit_exempt(*to);
+			if self._v2._max_tx_amount != 0 && !from_exempt && !to_exempt && amount > self._v2._max_tx_amount {
+				return Err(Error::MaxTxExceeded);
+			}
+			if self._v2._max_wallet_balance != 0 && !to_exempt && from != to
+				&& self.balance_of(*to).saturating_add(amount) > self._v2._max_wallet_balance {
+				return Err(Error::MaxWalletExceeded);
+			}
+
+			Ok(())
+		}
+
+		/// Takes the transfer fee and the transfer burn out of `amount`, returning what reaches `to`. Moves in
+		/// and out of the contract's own escrow, moves to the fee collector, the fee itself among them, and
+		/// exempt accounts are never charged.
+		fn _apply_transfer_charges(&mut self, from: &AccountId, to: &AccountId, amount: Balance) -> Result<Balance> {
+			let contract = self.env().account_id();
+			if *from == contract || *to == contract || Some(*to) == self._v2._fee_collector
+				|| self.is_fee_exempt(*from) || self.is_fee_exempt(*to) {
+				return Ok(amount);
+			}
+			let amount = self._collect_fee(from, amount)?;
+			let burned = Self::_bps_of(amount, self._v2._burn_rate_bps);
+			if burned > 0 {
+				self._burn(from, burned)?;
+			}
+
+			Ok(amount - burned)
+		}
+
+		fn _bps_of(amount: Balance, bps: u16) -> Balance {
+			let bps = Balance::from(bps);
+			amount / BPS_DENOMINATOR * bps + amount % BPS_DENOMINATOR * bps / BPS_DENOMINATOR
+		}
+
+		/// Pays the transfer fee from `from` to the fee collector and returns what is left of `amount`.
+		fn _collect_fee(&mut self, from: &AccountId, amount: Balance) -> Result<Balance> {
+			let fee = Self::_bps_of(amount, self._v2._fee_bps);
+			let collector = match self._v2._fee_collector {
+				Some(collector) if fee > 0 => collector,
+				_ => return Ok(amount),
+			};
+			self._move_balance(from, &collector, fee)?;
+
+			Self::env().emit_event(FeeCollected {
+				from: *from,
+				collector,
+				amount: fee,
+			});
+
+			Ok(amount - fee)
+		}
+
+		fn _move_balance(&mut self, from: &AccountId, to: &AccountId, amount: Balance) -> Result<()> {
+			if self.balance_of(*from) < amount {
+				return Err(Error::InsufficientBalance);
+			}
+			if from != to {
+				self.balance_of(*to).checked_add(amount).ok_or(Error::ArithmeticOverflow)?;
+			}
+			let amount = self._before_token_transfer(Some(from), Some(to), amount)?;
+			// Read after the hook, which may have taken the fee and burn out of `from` already.
+			let from_balance = self.balance_of(*from);
+			let new_from_balance = from_balance - amount;
+			// A self-transfer must leave the balance as it was, not credit it on top of the debit.
+			let new_to_balance = if from == to { from_balance } else { self.balance_of(*to) + amount };
+			self._set_balance(from, new_from_balance)?;
+			self._set_balance(to, new_to_balance)?;
+			self._after_token_transfer(Some(from), Some(to), amount);
+
+			Self::env().emit_event(Transferred {
+				from: *from,
+				to: *to,
+				value: amount,
+			});
+
+			Ok(())
+		}
+
+		fn _current_mint_period(&self) -> BlockNumber {
+			self.env().block_number().checked_div(self._v2._mint_period_length).unwrap_or(0)
+		}
+
+		fn _minted_in_current_period(&self) -> Balance {
+			if self._v2._mint_limit_per_period == 0 || self._v2._mint_period != self._current_mint_period() {
+				return 0;
+			}
+
+			self._v2._minted_in_period
+		}
+
+		fn _mint(&mut self, account: &AccountId, amount: Balance) -> Result<()> {
+			if self._max_mint_per_tx != 0 && amount > self._max_mint_per_tx {
+				return Err(Error::MintTooLarge);
+			}
+			let minted_in_period = self._minted_in_current_period().checked_add(amount).ok_or(Error::ArithmeticOverflow)?;
+			if self._v2._mint_limit_per_period != 0 && minted_in_period > self._v2._mint_limit_per_period {
+				return Err(Error::MintLimitExceeded);
+			}
+			let new_total_supply = self._total_supply.checked_add(amount).ok_or(Error::ArithmeticOverflow)?;
+			if self._v2._cap.is_some_and(|cap| new_total_supply > cap) {
+				return Err(Error::CapExceeded);
+			}
+			// Sale proceeds belong to the owner and cannot back wrapped supply.
+			if self._v2._wrapped && self.env().balance().saturating_sub(self._v2._sale_proceeds) < new_total_supply {
+				return Err(Error::Unbacked);
+			}
+			let account_balance = self.balance_of(*account);
+			let new_balance = account_balance.checked_add(amount).ok_or(Error::ArithmeticOverflow)?;
+			let new_total_gons = match self._v2._gons_per_fragment {
+				Some(gons_per_fragment) => amount.checked_mul(gons_per_fragment)
+					.and_then(|gons| self._v2._total_gons.checked_add(gons))
+					.ok_or(Error::ArithmeticOverflow)?,
+				None => 0,
+			};
+			self._before_token_transfer(None, Some(account), amount)?;
+			if self._v2._mint_limit_per_period != 0 {
+				self._v2._mint_period = self._current_mint_period();
+				self._v2._minted_in_period = minted_in_period;
+			}
+			self._total_supply = new_total_supply;
+			self._v2._total_gons = new_total_gons;
+			self._set_balance(account, new_balance)?;
+			self._after_token_transfer(None, Some(account), amount);
+
+			Self::env().emit_event(Minted {
+				to: *account,
+				value: amount,
+			});
+
+			Ok(())
+		}
+
+		fn _burn(&mut self, account: &AccountId, amount: Balance) -> Result<()> {
+			let balance = self.balance_of(*account);
+			let new_balance = balance.checked_sub(amount).ok_or(Error::InsufficientBalance)?;
+			let new_total_supply = self._total_supply.checked_sub(amount).ok_or(Error::ArithmeticOverflow)?;
+			let total_burned = self._v2._total_burned.checked_add(amount).ok_or(Error::ArithmeticOverflow)?;
+			let new_total_gons = match self._v2._gons_per_fragment {
+				Some(gons_per_fragment) => amount.checked_mul(gons_per_fragment)
+					.and_then(|gons| self._v2._total_gons.checked_sub(gons))
+					.ok_or(Error::ArithmeticOverflow)?,
+				None => 0,
+			};
+			self._before_token_transfer(Some(account), None, amount)?;
+			self._total_supply = new_total_supply;
+			self._v2._total_burned = total_burned;
+			self._v2._total_gons = new_total_gons;
+			self._set_balance(account, new_balance)?;
+			self._after_token_transfer(Some(account), None, amount);
+
+			Self::env().emit_event(Burned {
+				from: *account,
+				value: amount,
+			});
+
+			Ok(())
+		}
+
+		/// Runs before every balance change, once the caller has checked the change itself is possible.
+		/// `from` is `None` for mints and `to` is `None` for burns. Extensions refuse the change here, before
+		/// anything is written, then record whatever has to see the old balances. Returns how much of `amount`
+		/// goes on to `to`, which only the fee extension lowers, and only for transfers.
+		fn _before_token_transfer(&mut self, from: Option<&AccountId>, to: Option<&AccountId>, amount: Balance) -> Result<Balance> {
+			self.ensure_migrated()?;
+			if cfg!(feature = "pausable") {
+				self.ensure_not_paused()?;
+			}
+			let amount = match (from, to) {
+				(Some(from), Some(to)) if cfg!(feature = "fees") => self._apply_transfer_charges(from, to, amount)?,
+				_ => amount,
+			};
+
+			for account in [from, to].into_iter().flatten() {
+				self._update_twab(account);
+				if cfg!(feature = "snapshots") {
+					self._update_account_snapshot(account);
+				}
+			}
+			if cfg!(feature = "snapshots") && (from.is_none() || to.is_none()) {
+				self._update_total_supply_snapshot();
+			}
+			if self._v2._magnified_dividend_per_share != 0 {
+				// Offsets the dividends the moved tokens earned so far, so they stay with the old holder.
+				let magnified = (self._v2._magnified_dividend_per_share as i128).saturating_mul(amount as i128);
+				if let Some(from) = from {
+					self._correct_dividends(from, magnified);
+				}
+				if let Some(to) = to {
+					self._correct_dividends(to, magnified.saturating_neg());
+				}
+			}
+
+			Ok(amount)
+		}
+
+		/// Runs after every balance change, with `amount` as returned by `_before_token_transfer`.
+		fn _after_token_transfer(&mut self, from: Option<&AccountId>, to: Option<&AccountId>, amount: Balance) {
+			for account in [from, to].into_iter().flatten() {
+				self._update_holder(account);
+			}
+			if cfg!(feature = "votes") {
+				let src = from.and_then(|from| self.delegates(*from));
+				let dst = to.and_then(|to| self.delegates(*to));
+				self._move_votes(src, dst, amount);
+			}
+		}
+
+		fn _approve(&mut self, owner: &AccountId, spender: &AccountId, amount: Balance) {
+			self._v2._allowances.insert((owner, spender), &amount);
+			match (self._v2._spender_index.get((owner, spender)), amount) {
+				(None, amount) if amount > 0 => self._add_spender(owner, spender),
+				(Some(index), 0) => self._remove_spender(owner, index),
+				_ => {}
+			}
+
+			Self::env().emit_event(Approval {
+				owner: *owner,
+				spender: *spender,
+				value: amount,
+			});
+		}
+
+		fn _add_spender(&mut self, owner: &AccountId, spender: &AccountId) {
+			let index = self._v2._spender_counts.get(owner).unwrap_or(0);
+			self._v2._spenders.insert((owner, index), spender);
+			self._v2._spender_index.insert((owner, spender), &index);
+			self._v2._spender_counts.insert(owner, &(index + 1));
+		}
+
+		/// Moves `owner`'s last spender into slot `index` so the index stays dense.
+		fn _remove_spender(&mut self, owner: &AccountId, index: u32) {
+			let last = self._v2._spender_counts.get(owner).unwrap_or(0) - 1;
+			let spender = self._v2._spenders.get((owner, index)).expect("ERC20: spender index out of sync");
+			if index != last {
+				let moved = self._v2._spenders.get((owner, last)).expect("ERC20: spender index out of sync");
+				self._v2._spenders.insert((owner, index), &moved);
+				self._v2._spender_index.insert((owner, moved), &index);
+			}
+			self._v2._spenders.remove((owner, last));
+			self._v2._spender_index.remove((owner, spender));
+			self._v2._spender_counts.insert(owner, &last);
+		}
+
+		fn _is_allowance_expired(&self, owner: &AccountId, spender: &AccountId) -> bool {
+			self._v2._allowance_expiries.get((owner, spender))
+				.is_some_and(|expires_at| self.env().block_timestamp() >= expires_at)
+		}
+
+		/// Zero balances are pruned from storage; reads treat a missing entry as 0. In rebasing mode `balance`
+		/// is converted to gons, keeping the gons below one token that the account already had.
+		fn _set_balance(&mut self, account: &AccountId, balance: Balance) -> Result<()> {
+			let balance = match self._v2._gons_per_fragment {
+				Some(gons_per_fragment) => {
+					let dust = self._balances.get(account).unwrap_or(0) % gons_per_fragment;
+					balance.checked_mul(gons_per_fragment)
+						.and_then(|gons| gons.checked_add(dust))
+						.ok_or(Error::ArithmeticOverflow)?
+				}
+				None => balance,
+			};
+			if balance == 0 {
+				self._balances.remove(account);
+			} else {
+				self._balances.insert(account, &balance);
+			}
+
+			Ok(())
+		}
+
+		/// Adds or removes `account` as a holder to match its new balance. Checked against the index rather
+		/// than the old balance, so holders from before version 2 are registered on their first balance change.
+		fn _update_holder(&mut self, account: &AccountId) {
+			if self.balance_of(*account) == 0 {
+				self._remove_holder(account);
+			} else if !self._v2._holder_index.contains(account) {
+				self._add_holder(account);
+			}
+		}
+
+		fn _correct_dividends(&mut self, account: &AccountId, magnified: i128) {
+			let correction = self._v2._magnified_dividend_corrections.get(account).unwrap_or(0).saturating_add(magnified);
+			self._v2._magnified_dividend_corrections.insert(account, &correction);
+		}
+
+		fn _add_holder(&mut self, account: &AccountId) {
+			let index = self._v2._holder_count;
+			self._v2._holders.insert(index, account);
+			self._v2._holder_index.insert(account, &index);
+			self._v2._holder_count += 1;
+		}
+
+		/// Moves the last holder into `account`'s slot so the index stays dense.
+		fn _remove_holder(&mut self, account: &AccountId) {
+			let Some(index) = self._v2._holder_index.get(account) else {
+				return;
+			};
+			let last = self._v2._holder_count - 1;
+			if index != last {
+				let moved = self._v2._holders.get(last).expect("ERC20: holder index out of sync");
+				self._v2._holders.insert(index, &moved);
+				self._v2._holder_index.insert(moved, &index);
+			}
+			self._v2._holders.remove(last);
+			self._v2._holder_index.remove(account);
+			self._v2._holder_count = last;
+		}
+
+		/// Cumulative `balance * elapsed` of `account` from the epoch start up to `now`.
+		fn _cumulative_balance(&self, account: &AccountId, now: Timestamp) -> Balance {
+			// Anything accumulated before the epoch started belongs to an earlier one.
+			let (cumulative, updated_at) = self._twab_accumulators.get(account)
+				.filter(|(_, updated_at)| *updated_at > self._twab_start)
+				.unwrap_or((0, self._twab_start));
+			let balance = self.balance_of(*account);
+
+			cumulative.saturating_add(balance.saturating_mul(Balance::from(now - updated_at)))
+		}
+
+		/// Must be called before every balance change of `account`.
+		fn _update_twab(&mut self, account: &AccountId) {
+			let now = Self::env().block_timestamp();
+			let cumulative = self._cumulative_balance(account, now);
+			self._twab_accumulators.insert(account, &(cumulative, now));
+		}
+
+		/// Must be called before every balance change of `account`.
+		fn _update_account_snapshot(&mut self, account: &AccountId) {
+			let current = self._v2._current_snapshot_id;
+			if current == 0 {
+				return;
+			}
+			let count = self._v2._account_snapshot_counts.get(account).unwrap_or(0);
+			let last = count.checked_sub(1).and_then(|last| self._v2._account_snapshots.get((account, last)));
+			if last.is_none_or(|(id, _)| id < current) {
+				let balance = self.balance_of(*account);
+				self._v2._account_snapshots.insert((account, count), &(current, balance));
+				self._v2._account_snapshot_counts.insert(account, &(count + 1));
+			}
+		}
+
+		/// Must be called before every change of the total supply.
+		fn _update_total_supply_snapshot(&mut self) {
+			let current = self._v2._current_snapshot_id;
+			let count = self._v2._total_supply_snapshot_count;
+			let last = count.checked_sub(1).and_then(|last| self._v2._total_supply_snapshots.get(last));
+			if current != 0 && last.is_none_or(|(id, _)| id < current) {
+				self._v2._total_supply_snapshots.insert(count, &(current, self._total_supply));
+				self._v2._total_supply_snapshot_count = count + 1;
+			}
+		}
+
+		/// The value at `snapshot_id` given the `count` checkpoints `checkpoint` reads, or `current` if nothing
+		/// changed after it.
+		fn _value_at(
+			&self,
+			count: u32,
+			checkpoint: impl Fn(u32) -> Option<(SnapshotId, Balance)>,
+			snapshot_id: SnapshotId,
+			current: Balance,
+		) -> Result<Balance> {
+			if snapshot_id == 0 || snapshot_id > self._v2._current_snapshot_id {
+				return Err(Error::SnapshotNotFound);
+			}
+			let index = Self::_partition_point(count, |index| checkpoint(index).is_some_and(|(id, _)| id < snapshot_id));
+
+			Ok(checkpoint(index).map_or(current, |(_, value)| value))
+		}
+
+		/// Number of leading indices below `len` for which `pred` holds, `pred` being true for a prefix of them.
+		fn _partition_point(len: u32, pred: impl Fn(u32) -> bool) -> u32 {
+			let (mut low, mut high) = (0, len);
+			while low < high {
+				let mid = low + (high - low) / 2;
+				if pred(mid) {
+					low = mid + 1;
+				} else {
+					high = mid;
+				}
+			}
+
+			low
+		}
+
+		/// Shifts `amount` votes from delegate `src` to delegate `dst`, checkpointing both.
+		fn _move_votes(&mut self, src: Option<AccountId>, dst: Option<AccountId>, amount: Balance) {
+			if src == dst || amount == 0 {
+				return;
+			}
+			if let Some(src) = src {
+				let votes = self.get_votes(src);
+				self._write_vote_checkpoint(&src, votes, votes.saturating_sub(amount));
+			}
+			if let Some(dst) = dst {
+				let votes = self.get_votes(dst);
+				self._write_vote_checkpoint(&dst, votes, votes.saturating_add(amount));
+			}
+		}
+
+		fn _write_vote_checkpoint(&mut self, delegate: &AccountId, previous_votes: Balance, new_votes: Balance) {
+			let block = self.env().block_number();
+			let count = self._v2._vote_checkpoint_counts.get(delegate).unwrap_or(0);
+			match count.checked_sub(1) {
+				Some(last) if self._v2._vote_checkpoints.get((delegate, last)).is_some_and(|(at, _)| at == block) => {
+					self._v2._vote_checkpoints.insert((delegate, last), &(block, new_votes));
+				}
+				_ => {
+					self._v2._vote_checkpoints.insert((delegate, count), &(block, new_votes));
+					self._v2._vote_checkpoint_counts.insert(delegate, &(count + 1));
+				}
+			}
+
+			Self::env().emit_event(DelegateVotesChanged {
+				delegate: *delegate,
+				previous_votes,
+				new_votes,
+			});
+		}
+
+		fn _last_reward_block(&self) -> BlockNumber {
+			self.env().block_number().min(self._v2._reward_period_end).max(self._v2._reward_updated_at)
+		}
+
+		/// Must be called before every change of `account`'s stake or of the total stake.
+		fn _update_reward(&mut self, account: Option<AccountId>) {
+			let last = self._last_reward_block();
+			let blocks = Balance::from(last - self._v2._reward_updated_at);
+			self._v2._total_stake_blocks = self._v2._total_stake_blocks.saturating_add(self._v2._total_staked.saturating_mul(blocks));
+			self._v2._reward_updated_at = last;
+			if let Some(account) = account {
+				let (accrued, stake_blocks) = self._accrued_stake(&account);
+				let rewards = self._v2._rewards.get(account).unwrap_or(0) + accrued;
+				self._v2._rewards.insert(account, &rewards);
+				let epoch = self._v2._reward_epoch;
+				self._v2._stake_blocks.insert(account, &(epoch, stake_blocks, last));
+			}
+		}
+
+		/// The current reward epoch as it stands if it ended now.
+		fn _current_reward_epoch(&self) -> RewardEpoch {
+			let end = self._last_reward_block();
+			let start = self._v2._reward_epoch_start;
+			let total_stake_blocks = self._v2._total_stake_blocks
+				.saturating_add(self._v2._total_staked.saturating_mul(Balance::from(end - self._v2._reward_updated_at)));
+			let released = self._v2._reward_rate.saturating_mul(Balance::from(end - start));
+			// Nobody staked, so nobody earns; the rewards stay in the pool.
+			let reward_per_stake_block = released.saturating_mul(REWARD_PRECISION).checked_div(total_stake_blocks).unwrap_or(0);
+			let previous = self._v2._reward_epochs.get(self._v2._reward_epoch.saturating_sub(1)).unwrap_or_default();
+
+			RewardEpoch {
+				end,
+				reward_per_stake_block,
+				reward_per_token: previous.reward_per_token
+					.saturating_add(reward_per_stake_block.saturating_mul(Balance::from(end - start))),
+			}
+		}
+
+		/// `epoch` if it is over, the current one included once its rewards have run out.
+		fn _ended_reward_epoch(&self, epoch: u32) -> Option<RewardEpoch> {
+			if epoch < self._v2._reward_epoch {
+				return self._v2._reward_epochs.get(epoch);
+			}
+			if epoch == self._v2._reward_epoch && self.env().block_number() >= self._v2._reward_period_end {
+				return Some(self._current_reward_epoch());
+			}
+
+			None
+		}
+
+		fn _last_ended_reward_epoch(&self) -> RewardEpoch {
+			let current = self._v2._reward_epoch;
+			self._ended_reward_epoch(current)
+				.or_else(|| self._v2._reward_epochs.get(current.saturating_sub(1)))
+				.unwrap_or_default()
+		}
+
+		/// Rewards `account` earned in epochs that are over since it was last settled, and its stake summed over
+		/// the blocks of the current epoch that has not ended yet, or 0 once it has.
+		fn _accrued_stake(&self, account: &AccountId) -> (Balance, Balance) {
+			let stake = self.staked_of(*account);
+			let last = self._last_reward_block();
+			let current = self._v2._reward_epoch;
+			let Some((epoch, stake_blocks, updated_at)) = self._v2._stake_blocks.get(account) else {
+				return (0, 0);
+			};
+			let stake_blocks_until = |end: BlockNumber, since: BlockNumber| {
+				stake.saturating_mul(Balance::from(end.saturating_sub(since)))
+			};
+			let (mut magnified, mut stake_blocks) = if epoch == current {
+				(0, stake_blocks.saturating_add(stake_blocks_until(last, updated_at)))
+			} else {
+				// The epoch the account last settled in, then the whole epochs its stake sat through unchanged.
+				let settled = self._v2._reward_epochs.get(epoch).unwrap_or_default();
+				let previous = self._v2._reward_epochs.get(current.saturating_sub(1)).unwrap_or_default();
+				let magnified = stake_blocks.saturating_add(stake_blocks_until(settled.end, updated_at))
+					.saturating_mul(settled.reward_per_stake_block)
+					.saturating_add(stake.saturating_mul(previous.reward_per_token.saturating_sub(settled.reward_per_token)));
+				(magnified, stake_blocks_until(last, self._v2._reward_epoch_start))
+			};
+			if let Some(ended) = self._ended_reward_epoch(current) {
+				magnified = magnified.saturating_add(stake_blocks.saturating_mul(ended.reward_per_stake_block));
+				stake_blocks = 0;
+			}
+
+			(magnified / REWARD_PRECISION, stake_blocks)
+		}
+
+		/// The account a message acts on behalf of: the caller, or the sender
+		/// appended to the call data when relayed by the trusted forwarder.
+		fn _msg_sender(&self) -> AccountId {
+			let caller = self.env().caller();
+			if self._trusted_forwarder != Some(caller) {
+				return caller;
+			}
+
+			Self::_forwarded_sender().0
+		}
+
+		#[cfg(not(test))]
+		fn _forwarded_sender() -> ForwardedSender {
+			ink_env::decode_input::<ForwardedSender>()
+				.expect("ERC20: no forwarded sender in call data")
+		}
+
+		/// The off-chain environment has no call data, tests provide it through `tests::CALL_DATA`.
+		#[cfg(test)]
+		fn _forwarded_sender() -> ForwardedSender {
+			tests::CALL_DATA.with(|call_data| {
+				scale::Decode::decode(&mut &call_data.borrow()[..])
+			}).expect("ERC20: no forwarded sender in call data")
+		}
+
+		fn _balance_leaf(account: &AccountId, balance: Balance) -> Hash {
+			let mut leaf = [0u8; 32];
+			ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&(account, balance), &mut leaf);
+			Hash::from(leaf)
+		}
+
+		/// The account of the ecdsa key that signed `message`, derived the way Substrate does: Blake2x256 of the compressed public key.
+		fn _recover_signer(&self, message: &[u8; 32], signature: &[u8; 65]) -> Result<AccountId> {
+			let mut public_key = [0u8; 33];
+			ink_env::ecdsa_recover(signature, message, &mut public_key).map_err(|_| Error::InvalidSignature)?;
+			let mut account = [0u8; 32];
+			ink_env::hash_bytes::<ink_env::hash::Blake2x256>(&public_key, &mut account);
+			Ok(AccountId::from(account))
+		}
+
+		/// Pairs are hashed in sorted order, so proofs need no left/right flags.
+		fn _hash_pair(a: &Hash, b: &Hash) -> Hash {
+			let pair = if a <= b { (a, b) } else { (b, a) };
+			let mut node = [0u8; 32];
+			ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&pair, &mut node);
+			Hash::from(node)
+		}
+
+		fn ensure_migrated(&self) -> Result<()> {
+			if self.needs_migration() {
+				return Err(Error::MigrationPending);
+			}
+
+			Ok(())
+		}
+
+		fn ensure_not_paused(&self) -> Result<()> {
+			if self._v2._paused {
+				return Err(Error::Paused);
+			}
+
+			Ok(())
+		}
+
+		#[cfg(not(test))]
+		fn _is_condition_met(resolver: AccountId, condition_id: Hash) -> Result<bool> {
+			build_call::<Environment>()
+				.call_type(Call::new().callee(resolver))
+				.exec_input(
+					ExecutionInput::new(Selector::new(ink_lang::selector_bytes!("is_met")))
+						.push_arg(condition_id),
+				)
+				.returns::<bool>()
+				.fire()
+				.map_err(|_| Error::ResolverCallFailed)
+		}
+
+		/// The off-chain environment cannot call contracts, tests resolve through `tests::MET_CONDITIONS`.
+		#[cfg(test)]
+		fn _is_condition_met(_resolver: AccountId, condition_id: Hash) -> Result<bool> {
+			Ok(tests::MET_CONDITIONS.with(|met| met.borrow().contains(&condition_id)))
+		}
+
+		/// Root fields are only written back when the outer call returns, so they are flushed before the
+		/// receiver can call back in and reloaded afterwards; otherwise nested messages would run against
+		/// stale supply and holder counts, and the outer frame would overwrite what they wrote. The
+		/// off-chain seam below calls back on the same instance and cannot exercise this.
+		#[cfg(not(test))]
+		fn _on_flash_loan(&mut self, receiver: AccountId, initiator: AccountId, amount: Balance, fee: Balance, data: Vec<u8>) -> Result<bool> {
+			let root_key = ink_primitives::Key::from([0x00; 32]);
+			ink_storage::traits::push_spread_root(self, &root_key);
+			let accepted = build_call::<Environment>()
+				.call_type(Call::new().callee(receiver))
+				// The receiver calls back into `approve` to repay.
+				.call_flags(ink_env::CallFlags::default().set_allow_reentry(true))
+				.exec_input(
+					ExecutionInput::new(Selector::new(ink_lang::selector_bytes!("on_flash_loan")))
+						.push_arg(initiator)
+						.push_arg(amount)
+						.push_arg(fee)
+						.push_arg(data),
+				)
+				.returns::<bool>()
+				.fire()
+				.map_err(|_| Error::FlashLoanCallbackFailed);
+			*self = ink_storage::traits::pull_spread_root(&root_key);
+
+			accepted
+		}
+
+		/// The off-chain environment cannot call contracts, tests repay through `tests::FLASH_LOAN_APPROVAL`.
+		#[cfg(test)]
+		fn _on_flash_loan(&mut self, receiver: AccountId, _initiator: AccountId, _amount: Balance, _fee: Balance, _data: Vec<u8>) -> Result<bool> {
+			if tests::REENTER.with(|reenter| reenter.get()) {
+				let result = self.flash_loan(receiver, 1, Vec::new());
+				tests::REENTRANT_RESULT.with(|reentrant| *reentrant.borrow_mut() = Some(result));
+			}
+			match tests::FLASH_LOAN_APPROVAL.with(|approval| approval.get()) {
+				Some(approval) => {
+					self._approve(&receiver, &self.env().account_id(), approval);
+					Ok(true)
+				}
+				None => Ok(false),
+			}
+		}
+
+		#[cfg(not(test))]
+		fn _psp22_transfer(token: AccountId, to: AccountId, amount: Balance) -> Result<()> {
+			super::psp22_calls::transfer(token, to, amount, Vec::new())
+				.fire()
+				.map_err(|_| Error::RescueFailed)?
+				.map_err(|_| Error::RescueFailed)
+		}
+
+		/// The off-chain environment cannot call contracts, tests read the transfers from `tests::PSP22_TRANSFERS`.
+		#[cfg(test)]
+		fn _psp22_transfer(token: AccountId, to: AccountId, amount: Balance) -> Result<()> {
+			tests::PSP22_TRANSFERS.with(|transfers| transfers.borrow_mut().push((token, to, amount)));
+			Ok(())
+		}
+
+		fn _allowlist_permits(&self, from: &AccountId, to: &AccountId) -> bool {
+			!self._v2._allowlist_enabled || (self.is_allowlisted(*from) && self.is_allowlisted(*to))
+		}
+
+		#[cfg(not(test))]
+		fn _rules_restriction(rules: AccountId, from: &AccountId, to: &AccountId, amount: Balance) -> u8 {
+			build_call::<Environment>()
+				.call_type(Call::new().callee(rules))
+				.exec_input(
+					ExecutionInput::new(Selector::new(ink_lang::selector_bytes!("detect_transfer_restriction")))
+						.push_arg(from)
+						.push_arg(to)
+						.push_arg(amount),
+				)
+				.returns::<u8>()
+				.fire()
+				.unwrap_or(RESTRICTION_RULES_UNAVAILABLE)
+		}
+
+		#[cfg(not(test))]
+		fn _rules_message(rules: AccountId, code: u8) -> Option<String> {
+			build_call::<Environment>()
+				.call_type(Call::new().callee(rules))
+				.exec_input(
+					ExecutionInput::new(Selector::new(ink_lang::selector_bytes!("message_for_restriction_code")))
+						.push_arg(code),
+				)
+				.returns::<String>()
+				.fire()
+				.ok()
+		}
+
+		/// The off-chain environment cannot call contracts, tests set the rules' verdict in `tests::RULES_CODE`.
+		#[cfg(test)]
+		fn _rules_restriction(_rules: AccountId, _from: &AccountId, _to: &AccountId, _amount: Balance) -> u8 {
+			tests::RULES_CODE.with(|code| code.get())
+		}
+
+		#[cfg(test)]
+		fn _rules_message(_rules: AccountId, code: u8) -> Option<String> {
+			Some(ink_prelude::format!("Rule {}", code))
+		}
+
+		/// Runs `f` with the reentrancy lock held, failing if it is already held.
+		fn _non_reentrant<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+			if self._v2._reentrancy_lock.contains(()) {
+				return Err(Error::ReentrancyDetected);
+			}
+			self._v2._reentrancy_lock.insert((), &true);
+			let result = f(self);
+			self._v2._reentrancy_lock.remove(());
+
+			result
+		}
+
+		/// Asks `to` whether it accepts `value` tokens from `from`; plain accounts always do.
+		#[cfg(not(test))]
+		fn _safe_transfer_check(&mut self, from: &AccountId, to: &AccountId, value: Balance, data: Vec<u8>) -> Result<()> {
+			if !ink_env::is_contract::<Environment>(to) {
+				return Ok(());
+			}
+			build_call::<Environment>()
+				.call_type(Call::new().callee(*to))
+				.exec_input(
+					ExecutionInput::new(Selector::new(ink_lang::selector_bytes!("PSP22Receiver::before_received")))
+						.push_arg(self.env().caller())
+						.push_arg(*from)
+						.push_arg(value)
+						.push_arg(data),
+				)
+				.returns::<core::result::Result<(), super::PSP22ReceiverError>>()
+				.fire()
+				.map_err(|_| Error::SafeTransferCheckFailed(String::from("receiver call failed")))?
+				.map_err(|super::PSP22ReceiverError::TransferRejected(reason)| Error::SafeTransferCheckFailed(reason))
+		}
+
+		/// The off-chain environment has no contracts, tests mark rejecting receivers in `tests::REJECTING_RECEIVERS`.
+		#[cfg(test)]
+		fn _safe_transfer_check(&mut self, _from: &AccountId, to: &AccountId, _value: Balance, _data: Vec<u8>) -> Result<()> {
+			if tests::REENTER.with(|reenter| reenter.get()) {
+				let result = self.transfer_with_data(*to, 1, Vec::new());
+				tests::REENTRANT_RESULT.with(|reentrant| *reentrant.borrow_mut() = Some(result));
+			}
+			if tests::REJECTING_RECEIVERS.with(|receivers| receivers.borrow().contains(to)) {
+				return Err(Error::SafeTransferCheckFailed(String::from("rejected")));
+			}
+
+			Ok(())
+		}
+
+		fn only_role(&self, role: RoleId) -> Result<()> {
+			if !self.has_role(role, self._msg_sender()) {
+				return Err(Error::MissingRole);
+			}
+
+			Ok(())
+		}
+
+		fn _emit_metadata_updated(&self) {
+			Self::env().emit_event(MetadataUpdated {
+				name: self._name.clone(),
+				symbol: self._symbol.clone(),
+				metadata_uri: self._metadata_uri.clone(),
+			});
+		}
+
+		fn _pause(&mut self) -> Result<()> {
+			if !cfg!(feature = "pausable") {
+				return Err(Error::ExtensionDisabled);
+			}
+			self.ensure_not_paused()?;
+			self._v2._paused = true;
+
+			Self::env().emit_event(Paused { account: self._msg_sender() });
+
+			Ok(())
+		}
+
+		fn _unpause(&mut self) -> Result<()> {
+			if !self._v2._paused {
+				return Err(Error::NotPaused);
+			}
+			self._v2._paused = false;
+
+			Self::env().emit_event(Unpaused { account: self._msg_sender() });
+
+			Ok(())
+		}
+
+		fn _set_fee(&mut self, fee_bps: u16, collector: Option<AccountId>) -> Result<()> {
+			if !cfg!(feature = "fees") {
+				return Err(Error::ExtensionDisabled);
+			}
+			if fee_bps > MAX_FEE_BPS {
+				return Err(Error::FeeTooHigh);
+			}
+			self._v2._fee_bps = fee_bps;
+			self._v2._fee_collector = collector;
+
+			Ok(())
+		}
+
+		fn _start_ownership_transfer(&mut self, new_owner: AccountId) {
+			self._v2._pending_owner = Some(new_owner);
+
+			Self::env().emit_event(OwnershipTransferStarted {
+				previous_owner: self._owner,
+				new_owner,
+			});
+		}
+
+		/// Whether `action` must wait out the timelock delay while one is set.
+		fn _is_timelocked(action: &AdminAction) -> bool {
+			matches!(action, AdminAction::Mint { .. } | AdminAction::SetFee { .. } | AdminAction::Upgrade { .. })
+		}
+
+		fn _queue_operation(&mut self, operation: AdminAction) -> u64 {
+			let id = self._v2._next_operation_id;
+			self._v2._next_operation_id += 1;
+			let executable_at = self.env().block_number().saturating_add(self._v2._timelock_delay);
+			self._v2._queued_operations.insert(id, &QueuedOperation {
+				operation: operation.clone(),
+				executable_at,
+			});
+
+			Self::env().emit_event(OperationQueued { id, operation, executable_at });
+
+			id
+		}
+
+		fn _run_admin_action(&mut self, action: AdminAction) -> Result<()> {
+			match action {
+				AdminAction::Mint { to, amount } => self._mint(&to, amount)?,
+				AdminAction::Pause => self._pause()?,
+				AdminAction::Unpause => self._unpause()?,
+				AdminAction::SetFee { fee_bps, collector } => self._set_fee(fee_bps, collector)?,
+				AdminAction::TransferOwnership { new_owner } => self._start_ownership_transfer(new_owner),
+				AdminAction::Upgrade { code_hash } => self._upgrade(code_hash)?,
+			}
+
+			Ok(())
+		}
+
+		/// Everything `account` earned through `distribute`, withdrawn or not.
+		fn _accumulative_dividend_of(&self, account: &AccountId) -> Balance {
+			if *account == self.env().account_id() {
+				return 0;
+			}
+			let magnified = (self._v2._magnified_dividend_per_share as i128)
+				.saturating_mul(self.balance_of(*account) as i128)
+				.saturating_add(self._v2._magnified_dividend_corrections.get(account).unwrap_or(0));
+
+			magnified.max(0) as Balance / REWARD_PRECISION
+		}
+
+		/// Pays `escrow` to `to` if `caller` is its arbiter, or `party` while the escrow is undisputed.
+		fn _settle_escrow(&mut self, id: u64, escrow: &Escrow, caller: AccountId, party: AccountId, to: AccountId) -> Result<()> {
+			if caller != escrow.arbiter && (caller != party || escrow.disputed) {
+				return Err(Error::NotEscrowParty);
+			}
+			self._transfer(&self.env().account_id(), &to, escrow.amount)?;
+			self._v2._escrows.remove(id);
+
+			Ok(())
+		}
+
+		fn _upgrade(&mut self, code_hash: Hash) -> Result<()> {
+			Self::_set_code_hash(code_hash)?;
+
+			Self::env().emit_event(Upgraded { code_hash });
+
+			Ok(())
+		}
+
+		#[cfg(not(test))]
+		fn _set_code_hash(code_hash: Hash) -> Result<()> {
+			let mut bytes = [0u8; 32];
+			bytes.copy_from_slice(code_hash.as_ref());
+			ink_env::set_code_hash(&bytes).map_err(|_| Error::UpgradeFailed)
+		}
+
+		/// The off-chain environment cannot swap code, tests read the new hash from `tests::UPGRADED_TO`.
+		#[cfg(test)]
+		fn _set_code_hash(code_hash: Hash) -> Result<()> {
+			tests::UPGRADED_TO.with(|upgraded| upgraded.set(Some(code_hash)));
+
+			Ok(())
+		}
+
+		fn _revoke_role(&mut self, role: RoleId, account: AccountId) {
+			if !self.has_role(role, account) {
+				return;
+			}
+			self._v2._roles.remove((role, account));
+
+			Self::env().emit_event(RoleRevoked {
+				role,
+				account,
+				sender: self._msg_sender(),
+			});
+		}
+
+		fn ensure_not_zero(account: &AccountId) -> Result<()> {
+			if *account == AccountId::from([0u8; 32]) {
+				return Err(Error::ZeroAddress);
+			}
+
+			Ok(())
+		}
+
+		fn ensure_not_timelocked(&self) -> Result<()> {
+			if self._v2._timelock_delay != 0 {
+				return Err(Error::TimelockRequired);
+			}
+
+			Ok(())
+		}
+
+		fn only_admin_signer(&self) -> Result<AccountId> {
+			let caller = self._msg_sender();
+			if !self._v2._admin_signers.contains(&caller) {
+				return Err(Error::NotAdminSigner);
+			}
+
+			Ok(caller)
+		}
+
+		fn only_allowed_caller(&self) -> Result<()> {
+			// A zero owner means nobody is in charge, even a caller spoofing the zero account.
+			if self._owner == AccountId::from([0u8; 32]) || self._owner != self._msg_sender() {
+				return Err(Error::NotOwner);
+			}
+
+			Ok(())
+        }
+    }
+    impl PSP22 for Erc20 {
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            self._total_supply
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance {
+            Erc20::balance_of(self, owner)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            Erc20::allowance(self, owner, spender)
+        }
+
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, value: Balance, data: Vec<u8>) -> core::result::Result<(), PSP22Error> {
+			Ok(Erc20::transfer_with_data(self, to, value, data)?)
+        }
+
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> core::result::Result<(), PSP22Error> {
+			Ok(Erc20::transfer_from(self, from, to, value)?)
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> core::result::Result<(), PSP22Error> {
+			Ok(Erc20::approve(self, spender, value)?)
+        }
+
+        #[ink(message)]
+        fn increase_allowance(&mut self, spender: AccountId, delta_value: Balance) -> core::result::Result<(), PSP22Error> {
+			Ok(Erc20::increase_allowance(self, spender, delta_value)?)
+        }
+
+        #[ink(message)]
+        fn decrease_allowance(&mut self, spender: AccountId, delta_value: Balance) -> core::result::Result<(), PSP22Error> {
+			Ok(Erc20::decrease_allowance(self, spender, delta_value)?)
+        }
+    }
+
+    impl PSP22Metadata for Erc20 {
+        #[ink(message)]
+        fn token_name(&self) -> Option<String> {
+            Some(self._name.clone())
+        }
+
+        #[ink(message)]
+        fn token_symbol(&self) -> Option<String> {
+            Some(self._symbol.clone())
+        }
+
+        #[ink(message)]
+        fn token_decimals(&self) -> u8 {
+            self._v2._decimals
+        }
+    }
+
+    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
+    /// module and test functions are marked with a `#[test]` attribute.
+    /// The below code is technically just normal Rust code.
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+
+        /// Imports `ink_lang` so we can use `#[ink::test]`.
+        use ink_lang as ink;
+
+        use std::cell::{Cell, RefCell};
+
+        type Event = <Erc20 as ::ink_lang::reflect::ContractEventBase>::Type;
+
+        thread_local! {
+            /// Stand-in for the call data of the current call, which the off-chain env lacks.
+            pub static CALL_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+
+            /// Conditions the mock resolver reports as met.
+            pub static MET_CONDITIONS: RefCell<Vec<Hash>> = const { RefCell::new(Vec::new()) };
+
+            /// What the mock flash loan receiver approves for repayment, `None` to reject the loan.
+            pub static FLASH_LOAN_APPROVAL: Cell<Option<Balance>> = const { Cell::new(None) };
+
+            /// Accounts that act as contracts refusing every `before_received`.
+            pub static REJECTING_RECEIVERS: RefCell<Vec<AccountId>> = const { RefCell::new(Vec::new()) };
+
+            /// Makes the mock receiver and flash borrower call back into the message that called them.
+            pub static REENTER: Cell<bool> = const { Cell::new(false) };
+
+            /// What the last such re-entrant call returned.
+            pub static REENTRANT_RESULT: RefCell<Option<Result<()>>> = const { RefCell::new(None) };
+
+            /// The code hash the last upgrade switched to.
+            pub static UPGRADED_TO: Cell<Option<Hash>> = const { Cell::new(None) };
+
+            /// The code the mock transfer rules contract returns.
+            pub static RULES_CODE: Cell<u8> = const { Cell::new(RESTRICTION_SUCCESS) };
+
+            /// `(token, to, amount)` of every foreign PSP22 transfer made.
+            pub static PSP22_TRANSFERS: RefCell<Vec<(AccountId, AccountId, Balance)>> = const { RefCell::new(Vec::new()) };
+        }
+
+        /// Gives the contract its own account so escrowed tokens do not land on alice.
+        fn set_contract_account() -> AccountId {
+            let contract = AccountId::from([0x42; 32]);
+            ink_env::test::set_callee::<ink_env::DefaultEnvironment>(contract);
+            contract
+        }
+
+        /// Builds the call data a forwarder would relay: selector, arguments, then the real sender.
+        fn forwarded_call(args: &[u8], sender: AccountId) -> Vec<u8> {
+            let mut call_data = vec![0u8; 4];
+            call_data.extend_from_slice(args);
+            call_data.extend_from_slice(sender.as_ref());
+            call_data
+        }
+
+        /// We test a simple use case of our contract.
+        #[ink::test]
+        fn it_constructor_works() {
+            let erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+            assert_eq!(erc20.name(), "Polkadot");
+            assert_eq!(erc20.symbol(), "DOT");
+            assert_eq!(erc20.decimals(), 10);
+            assert_eq!(erc20.total_supply(), 1000);
+        }
+
+		#[ink::test]
+        fn it_transfer_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			let bob_balance = erc20.balance_of(accounts.bob);
+
+			assert_eq!(bob_balance, 0);
+
+            assert_eq!(erc20.transfer(accounts.bob, 1), Ok(()));
+
+            assert_eq!(erc20.balance_of(accounts.bob), 1);
+
+            // ink_env::debug_println!("balance 1 {:?} - {:?}", bob_balance, erc20.balance_of(accounts.bob));
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+		}
+
+		#[ink::test]
+        fn it_mint_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			let alice_balance = erc20.balance_of(accounts.alice);
+
+            assert_eq!(erc20.mint(10), Ok(()));
+
+            assert_eq!(erc20.balance_of(accounts.alice), alice_balance + 10);
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+		}
+
+		#[ink::test]
+        fn it_burn_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			let alice_balance = erc20.balance_of(accounts.alice);
+
+            assert_eq!(erc20.burn(10), Ok(()));
+
+            assert_eq!(erc20.balance_of(accounts.alice), alice_balance - 10);
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+		}
+
+		#[ink::test]
+        fn it_twab_rewards_steady_holders() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+			for _ in 0..10 {
+				ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			}
+			assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+			// Both hold the same balance now, but bob held it for the whole epoch.
+			assert_eq!(erc20.balance_of(accounts.bob), erc20.balance_of(accounts.charlie));
+			assert_eq!(erc20.twab(accounts.bob), 100);
+			assert!(erc20.twab(accounts.charlie) < 10);
+			assert!(erc20.twab(accounts.bob) > erc20.twab(accounts.charlie));
+
+			// A reward epoch starts the averages over. Bob stakes for all of it, charlie only for the last block.
+			assert_eq!(erc20.fund_rewards(660, 10), Ok(()));
+			assert_eq!(erc20.twab(accounts.charlie), 100);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.stake(100), Ok(()));
+			for _ in 0..9 {
+				ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			}
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.stake(100), Ok(()));
+			assert_eq!(erc20.pending_rewards(accounts.bob), 0);
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.twab(accounts.alice), 140);
+			assert_eq!(erc20.twab(accounts.bob), 0);
+			assert_eq!(erc20.twab(accounts.charlie), 90);
+			// Both end the epoch with the same stake, but it is shared 1000 stake-blocks to 100.
+			assert_eq!(erc20.pending_rewards(accounts.bob), 600);
+			assert_eq!(erc20.pending_rewards(accounts.charlie), 60);
+		}
+
+		#[ink::test]
+        fn it_forwarded_transfer_uses_real_sender() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.set_trusted_forwarder(Some(accounts.django)), Ok(()));
+			assert_eq!(erc20.trusted_forwarder(), Some(accounts.django));
+
+			let args = scale::Encode::encode(&(accounts.bob, 10 as Balance));
+			CALL_DATA.with(|call_data| *call_data.borrow_mut() = forwarded_call(&args, accounts.alice));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.django);
+
+			assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 990);
+			assert_eq!(erc20.balance_of(accounts.bob), 10);
+			assert_eq!(erc20.balance_of(accounts.django), 0);
+		}
+
+		#[ink::test]
+        fn it_untrusted_caller_is_not_forwarded() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.set_trusted_forwarder(Some(accounts.django)), Ok(()));
+			CALL_DATA.with(|call_data| *call_data.borrow_mut() = forwarded_call(&[], accounts.bob));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+
+			assert_eq!(erc20.transfer(accounts.charlie, 0), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 1000);
+		}
+
+		#[ink::test]
+        fn it_zero_owner_allows_nobody() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.renounce_ownership(), Ok(()));
+			assert_eq!(erc20.owner(), AccountId::from([0u8; 32]));
+			assert!(OWNER_ROLES.iter().all(|role| !erc20.has_role(*role, accounts.alice)));
+
+			let callers = [
+				AccountId::from([0u8; 32]),
+				accounts.alice,
+				accounts.bob,
+				accounts.charlie,
+				accounts.django,
+				accounts.eve,
+				accounts.frank,
+			];
+			for caller in callers {
+				ink_env::test::set_caller::<ink_env::DefaultEnvironment>(caller);
+				assert_eq!(erc20.mint(1), Err(Error::MissingRole));
+				assert_eq!(erc20.burn(1), Err(Error::MissingRole));
+				assert_eq!(erc20.grant_role(MINTER_ROLE, caller), Err(Error::NotOwner));
+			}
+			assert_eq!(erc20.total_supply(), 1000);
+		}
+
+		#[ink::test]
+        fn it_multicall_read_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+
+			assert_eq!(erc20.approve(accounts.charlie, 30), Ok(()));
+
+			let results = erc20.multicall_read(vec![
+				ReadCall::BalanceOf(accounts.alice),
+				ReadCall::TotalSupply,
+				ReadCall::Allowance(accounts.alice, accounts.charlie),
+				ReadCall::BalanceOf(accounts.bob),
+				ReadCall::Allowance(accounts.bob, accounts.charlie),
+				ReadCall::BalanceOf(accounts.charlie),
+			]);
+			assert_eq!(results, Ok(vec![900, 1000, 30, 100, 0, 0]));
+		}
+
+		#[ink::test]
+        fn it_multicall_read_is_bounded() {
+    		let erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			assert_eq!(erc20.multicall_read(vec![ReadCall::TotalSupply; MAX_READ_CALLS + 1]), Err(Error::TooManyReadCalls));
+		}
+
+		#[ink::test]
+        fn it_pruned_balance_reads_as_zero() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer(accounts.alice, 100), Ok(()));
+			assert!(!erc20._balances.contains(accounts.bob));
+
+			assert_eq!(erc20.balance_of(accounts.bob), 0);
+			assert_eq!(erc20.twab(accounts.bob), 100);
+			assert_eq!(erc20.multicall_read(vec![ReadCall::BalanceOf(accounts.bob)]), Ok(vec![0]));
+		}
+
+		#[ink::test]
+        fn it_set_metadata_uri_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			assert_eq!(erc20.metadata_uri(), "");
+
+			assert_eq!(erc20.set_metadata_uri("ipfs://bafy/dot.json".to_string()), Ok(()));
+			assert_eq!(erc20.metadata_uri(), "ipfs://bafy/dot.json");
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			// Initial mint, `MetadataUpdated`, `TokenUriUpdated`.
+            assert_eq!(emitted_events.len(), 3);
+		}
+
+		#[ink::test]
+        fn it_set_metadata_uri_rejects_long_uri() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			assert_eq!(erc20.set_metadata_uri("a".repeat(MAX_METADATA_URI_LEN + 1)), Err(Error::MetadataUriTooLong));
+		}
+
+		#[ink::test]
+        fn it_mint_at_max_per_tx_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			assert_eq!(erc20.set_max_mint_per_tx(50), Ok(()));
+			assert_eq!(erc20.max_mint_per_tx(), 50);
+
+			assert_eq!(erc20.mint(50), Ok(()));
+			assert_eq!(erc20.total_supply(), 1050);
+		}
+
+		#[ink::test]
+        fn it_mint_over_max_per_tx_fails() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			assert_eq!(erc20.set_max_mint_per_tx(50), Ok(()));
+			assert_eq!(erc20.mint(51), Err(Error::MintTooLarge));
+		}
+
+		#[ink::test]
+        fn it_adjusts_allowance_incrementally() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.increase_allowance(AccountId::from([0u8; 32]), 100), Err(Error::ZeroAddress));
+			assert_eq!(erc20.increase_allowance(accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.increase_allowance(accounts.bob, 50), Ok(()));
+			assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 150);
+
+			assert_eq!(erc20.decrease_allowance(accounts.bob, 151), Err(Error::InsufficientAllowance));
+			assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 150);
+			assert_eq!(erc20.decrease_allowance(accounts.bob, 150), Ok(()));
+			assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+
+			assert_eq!(erc20.increase_allowance(accounts.bob, Balance::MAX), Ok(()));
+			assert_eq!(erc20.increase_allowance(accounts.bob, 1), Ok(()));
+			assert_eq!(erc20.allowance(accounts.alice, accounts.bob), Balance::MAX);
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			// Constructor transfer plus one Approval per successful adjustment.
+			assert_eq!(emitted_events.len(), 6);
+		}
+
+		fn ecdsa_account(secret: &secp256k1::SecretKey) -> AccountId {
+			let public_key = secp256k1::PublicKey::from_secret_key(secp256k1::SECP256K1, secret).serialize();
+			let mut account = [0u8; 32];
+			ink_env::hash_bytes::<ink_env::hash::Blake2x256>(&public_key, &mut account);
+			AccountId::from(account)
+		}
+
+		fn ecdsa_sign<T: scale::Encode>(secret: &secp256k1::SecretKey, payload: &T) -> [u8; 65] {
+			let mut hash = [0u8; 32];
+			ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(payload, &mut hash);
+			let message = secp256k1::Message::from_slice(&hash).unwrap();
+			let (recovery_id, compact) = secp256k1::SECP256K1
+				.sign_ecdsa_recoverable(&message, secret)
+				.serialize_compact();
+			let mut signature = [0u8; 65];
+			signature[..64].copy_from_slice(&compact);
+			signature[64] = recovery_id.to_i32() as u8;
+			signature
+		}
+
+		#[ink::test]
+        fn it_permit_sets_allowance_from_signature() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			let secret = secp256k1::SecretKey::from_slice(&[7u8; 32]).unwrap();
+			let owner = ecdsa_account(&secret);
+			let domain = erc20.domain_separator();
+
+			let signature = ecdsa_sign(&secret, &(domain, PERMIT_TYPE, owner, accounts.bob, 100 as Balance, 0u64, 1000 as Timestamp));
+			assert_eq!(erc20.permit(owner, accounts.charlie, 100, 1000, signature), Err(Error::InvalidSignature));
+			let zero = AccountId::from([0u8; 32]);
+			let zero_signature = ecdsa_sign(&secret, &(domain, PERMIT_TYPE, owner, zero, 100 as Balance, 0u64, 1000 as Timestamp));
+			assert_eq!(erc20.permit(owner, zero, 100, 1000, zero_signature), Err(Error::ZeroAddress));
+			assert_eq!(erc20.permit(owner, accounts.bob, 100, 1000, signature), Ok(()));
+			assert_eq!(erc20.allowance(owner, accounts.bob), 100);
+			assert_eq!(erc20.nonce_of(owner), 1);
+
+			// The nonce moved on, so the same signature cannot be replayed.
+			assert_eq!(erc20.permit(owner, accounts.bob, 100, 1000, signature), Err(Error::InvalidSignature));
+
+			let signature = ecdsa_sign(&secret, &(domain, PERMIT_TYPE, owner, accounts.bob, 0 as Balance, 1u64, 0 as Timestamp));
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.permit(owner, accounts.bob, 0, 0, signature), Err(Error::PermitExpired));
+			assert_eq!(erc20.allowance(owner, accounts.bob), 100);
+		}
+
+		#[ink::test]
+        fn it_transfer_with_authorization_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			let secret = secp256k1::SecretKey::from_slice(&[9u8; 32]).unwrap();
+			let from = ecdsa_account(&secret);
+			let domain = erc20.domain_separator();
+			let nonce = Hash::from([1u8; 32]);
+			assert_eq!(erc20.transfer(from, 100), Ok(()));
+
+			let payload = (domain, TRANSFER_WITH_AUTHORIZATION_TYPE, from, accounts.bob, 40 as Balance, 0 as Timestamp, 100 as Timestamp, nonce);
+			let signature = ecdsa_sign(&secret, &payload);
+			assert_eq!(
+				erc20.transfer_with_authorization(from, accounts.bob, 40, 0, 100, nonce, signature),
+				Err(Error::AuthorizationNotYetValid)
+			);
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(
+				erc20.transfer_with_authorization(from, accounts.charlie, 40, 0, 100, nonce, signature),
+				Err(Error::InvalidSignature)
+			);
+			assert_eq!(erc20.transfer_with_authorization(from, accounts.bob, 40, 0, 100, nonce, signature), Ok(()));
+			assert_eq!(erc20.balance_of(from), 60);
+			assert_eq!(erc20.balance_of(accounts.bob), 40);
+			assert!(erc20.authorization_state(from, nonce));
+			assert_eq!(
+				erc20.transfer_with_authorization(from, accounts.bob, 40, 0, 100, nonce, signature),
+				Err(Error::AuthorizationUsed)
+			);
+
+			let nonce = Hash::from([2u8; 32]);
+			let payload = (domain, TRANSFER_WITH_AUTHORIZATION_TYPE, from, accounts.bob, 40 as Balance, 0 as Timestamp, 6 as Timestamp, nonce);
+			let signature = ecdsa_sign(&secret, &payload);
+			assert_eq!(
+				erc20.transfer_with_authorization(from, accounts.bob, 40, 0, 6, nonce, signature),
+				Err(Error::AuthorizationExpired)
+			);
+			assert!(!erc20.authorization_state(from, nonce));
+		}
+
+		#[ink::test]
+        #[cfg(all(feature = "pausable", feature = "snapshots"))]
+        fn it_freeze_and_snapshot_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.freeze_and_snapshot(), Err(Error::MissingRole));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.freeze_and_snapshot(), Ok(1));
+			assert!(erc20.paused());
+			assert_eq!(erc20.transfer(accounts.bob, 50), Err(Error::Paused));
+			assert_eq!(erc20.freeze_and_snapshot(), Err(Error::Paused));
+			assert_eq!(erc20.current_snapshot_id(), 1);
+
+			assert_eq!(erc20.unpause(), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 50), Ok(()));
+			assert_eq!(erc20.balance_of_at(accounts.bob, 1), Ok(100));
+			assert_eq!(erc20.balance_of_at(accounts.alice, 1), Ok(900));
+			assert_eq!(erc20.balance_of(accounts.bob), 150);
+		}
+
+		#[ink::test]
+        #[cfg(feature = "snapshots")]
+        fn it_snapshots_record_historical_balances() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.balance_of_at(accounts.alice, 1), Err(Error::SnapshotNotFound));
+			assert_eq!(erc20.snapshot(), Ok(1));
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.mint(50), Ok(()));
+			assert_eq!(erc20.snapshot(), Ok(2));
+			assert_eq!(erc20.snapshot(), Ok(3));
+			assert_eq!(erc20.burn(200), Ok(()));
+
+			assert_eq!(erc20.balance_of_at(accounts.alice, 1), Ok(1000));
+			assert_eq!(erc20.balance_of_at(accounts.bob, 1), Ok(0));
+			assert_eq!(erc20.total_supply_at(1), Ok(1000));
+			assert_eq!(erc20.balance_of_at(accounts.alice, 2), Ok(950));
+			assert_eq!(erc20.balance_of_at(accounts.alice, 3), Ok(950));
+			assert_eq!(erc20.balance_of_at(accounts.bob, 3), Ok(100));
+			assert_eq!(erc20.total_supply_at(3), Ok(1050));
+			assert_eq!(erc20.balance_of(accounts.alice), 750);
+			assert_eq!(erc20.total_supply_at(0), Err(Error::SnapshotNotFound));
+			assert_eq!(erc20.total_supply_at(4), Err(Error::SnapshotNotFound));
+
+			assert_eq!(erc20.mint(1000), Ok(()));
+			for _ in 0..1000 {
+				assert!(erc20.snapshot().is_ok());
+				assert_eq!(erc20.burn(1), Ok(()));
+			}
+			assert_eq!(erc20.balance_of_at(accounts.alice, 503), Ok(1251));
+			assert_eq!(erc20.total_supply_at(503), Ok(1351));
+			assert_eq!(erc20.balance_of_at(accounts.bob, 503), Ok(100));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.snapshot(), Err(Error::MissingRole));
+			assert_eq!(erc20.current_snapshot_id(), 1003);
+		}
+
+		#[ink::test]
+        #[cfg(feature = "votes")]
+        fn it_delegated_votes_are_checkpointed() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.get_votes(accounts.alice), 0);
+			assert_eq!(erc20.delegate(accounts.alice), Ok(()));
+			assert_eq!(erc20.delegates(accounts.alice), Some(accounts.alice));
+			assert_eq!(erc20.get_votes(accounts.alice), 1000);
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.transfer(accounts.bob, 300), Ok(()));
+			assert_eq!(erc20.get_votes(accounts.alice), 700);
+			// Bob has not delegated, so his balance carries no votes yet.
+			assert_eq!(erc20.get_votes(accounts.bob), 0);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.delegate(accounts.charlie), Ok(()));
+			assert_eq!(erc20.get_votes(accounts.charlie), 300);
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.mint(100), Ok(()));
+			assert_eq!(erc20.burn(50), Ok(()));
+			assert_eq!(erc20.get_votes(accounts.alice), 750);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.delegate(accounts.alice), Ok(()));
+			assert_eq!(erc20.get_votes(accounts.alice), 1050);
+			assert_eq!(erc20.get_votes(accounts.charlie), 0);
+
+			assert_eq!(erc20.get_past_votes(accounts.alice, 0), Ok(1000));
+			assert_eq!(erc20.get_past_votes(accounts.alice, 1), Ok(700));
+			assert_eq!(erc20.get_past_votes(accounts.charlie, 1), Ok(300));
+			assert_eq!(erc20.get_past_votes(accounts.alice, 2), Err(Error::BlockNotYetMined));
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.get_past_votes(accounts.alice, 2), Ok(1050));
+			assert_eq!(erc20.get_past_votes(accounts.charlie, 2), Ok(0));
+
+			// A checkpoint every block for longer than one storage cell could hold as a list.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.mint(1000), Ok(()));
+			for _ in 0..1000 {
+				ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+				assert_eq!(erc20.transfer(accounts.django, 1), Ok(()));
+			}
+			assert_eq!(erc20.get_votes(accounts.alice), 1050);
+			assert_eq!(erc20.get_past_votes(accounts.alice, 503), Ok(1550));
+		}
+
+		#[ink::test]
+        fn it_vesting_releases_linearly_after_the_cliff() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.create_vesting_schedule(accounts.bob, 0, 12, 0, 600), Err(Error::InvalidVestingSchedule));
+			assert_eq!(erc20.create_vesting_schedule(accounts.bob, 0, 12, 60, 600), Ok(()));
+			assert_eq!(erc20.create_vesting_schedule(accounts.bob, 0, 12, 60, 1), Err(Error::VestingScheduleExists));
+			assert_eq!(erc20.balance_of(accounts.alice), 400);
+			assert_eq!(erc20.balance_of(contract), 600);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.releasable(accounts.bob), 0);
+			assert_eq!(erc20.release(), Err(Error::NothingToRelease));
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.releasable(accounts.bob), 120);
+			assert_eq!(erc20.release(), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 120);
+			assert_eq!(erc20.releasable(accounts.bob), 0);
+
+			for _ in 0..8 {
+				ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			}
+			assert_eq!(erc20.releasable(accounts.bob), 480);
+			assert_eq!(erc20.release(), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 600);
+			assert_eq!(erc20.vesting_schedule(accounts.bob), None);
+			assert_eq!(erc20.release(), Err(Error::NoVestingSchedule));
+		}
+
+		#[ink::test]
+        fn it_revoke_vesting_splits_vested_and_unvested() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.create_vesting_schedule(accounts.bob, 0, 0, 60, 600), Ok(()));
+			for _ in 0..3 {
+				ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			}
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.revoke_vesting(accounts.bob), Err(Error::NotOwner));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.revoke_vesting(accounts.bob), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 180);
+			assert_eq!(erc20.balance_of(accounts.alice), 820);
+			assert_eq!(erc20.balance_of(contract), 0);
+			assert_eq!(erc20.revoke_vesting(accounts.bob), Err(Error::NoVestingSchedule));
+		}
+
+		#[ink::test]
+        fn it_time_locked_transfers_are_claimed_when_mature() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.transfer_locked(accounts.bob, 100, 6), Ok(()));
+			assert_eq!(erc20.transfer_locked(accounts.bob, 50, 12), Ok(()));
+			assert_eq!(erc20.balance_of(contract), 150);
+			assert_eq!(erc20.locked_balance_of(accounts.bob), 150);
+			assert_eq!(erc20.locks_of(accounts.bob), vec![
+				TimeLock { from: accounts.alice, amount: 100, unlock_at: 6 },
+				TimeLock { from: accounts.alice, amount: 50, unlock_at: 12 },
+			]);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.claim_unlocked(), Err(Error::NothingUnlocked));
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.claim_unlocked(), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.locked_balance_of(accounts.bob), 50);
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.claim_unlocked(), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 150);
+			assert_eq!(erc20.locks_of(accounts.bob), vec![]);
+			assert_eq!(erc20.balance_of(contract), 0);
+		}
+
+		#[ink::test]
+        fn it_transfer_locked_caps_pending_locks() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			for _ in 0..MAX_LOCKS_PER_ACCOUNT {
+				assert_eq!(erc20.transfer_locked(accounts.bob, 1, 6), Ok(()));
+			}
+			assert_eq!(erc20.transfer_locked(accounts.bob, 1, 6), Err(Error::TooManyLocks));
+			assert_eq!(erc20.transfer_locked(accounts.bob, 2000, 6), Err(Error::TooManyLocks));
+			assert_eq!(erc20.locked_balance_of(accounts.bob), MAX_LOCKS_PER_ACCOUNT as Balance);
+
+			// The recipient frees a slot by sending an unwanted lock back.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.reject_lock(MAX_LOCKS_PER_ACCOUNT as u32), Err(Error::LockNotFound));
+			assert_eq!(erc20.reject_lock(0), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 1000 - MAX_LOCKS_PER_ACCOUNT as Balance + 1);
+			assert_eq!(erc20.locks_of(accounts.bob).len(), MAX_LOCKS_PER_ACCOUNT - 1);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.transfer_locked(accounts.bob, 1, 6), Ok(()));
+		}
+
+		#[ink::test]
+        #[cfg(feature = "fees")]
+        fn it_transfer_fee_goes_to_collector() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.set_fee(MAX_FEE_BPS + 1, Some(accounts.eve)), Err(Error::FeeTooHigh));
+			assert_eq!(erc20.set_fee(250, Some(accounts.eve)), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 400), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 390);
+			assert_eq!(erc20.balance_of(accounts.eve), 10);
+			assert_eq!(erc20.balance_of(accounts.alice), 600);
+
+			// Initial mint, then the fee leg's Transferred and FeeCollected, then the net Transferred.
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 4);
+			match <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..]).unwrap() {
+				Event::FeeCollected(event) => {
+					assert_eq!((event.from, event.collector, event.amount), (accounts.alice, accounts.eve, 10));
+				}
+				_ => panic!("expected a FeeCollected event"),
+			}
+
+			assert_eq!(erc20.transfer(accounts.bob, 601), Err(Error::InsufficientBalance));
+			assert_eq!(erc20.set_fee_exempt(accounts.bob, true), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 490);
+
+			assert_eq!(erc20.set_fee(250, None), Ok(()));
+			assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.charlie), 100);
+			assert_eq!(erc20.balance_of(accounts.eve), 10);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.set_fee(0, None), Err(Error::NotOwner));
+			assert_eq!(erc20.set_fee_exempt(accounts.bob, false), Err(Error::NotOwner));
+		}
+
+		#[ink::test]
+        #[cfg(feature = "fees")]
+        fn it_burns_a_share_of_each_transfer() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.set_burn_rate_bps(MAX_BURN_RATE_BPS + 1), Err(Error::BurnRateTooHigh));
+			assert_eq!(erc20.set_burn_rate_bps(100), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 200), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 198);
+			assert_eq!(erc20.total_supply(), 998);
+			assert_eq!(erc20.total_burned(), 2);
+
+			// With a 5% fee as well, the burn applies to what is left after the fee.
+			assert_eq!(erc20.set_fee(500, Some(accounts.eve)), Ok(()));
+			assert_eq!(erc20.transfer(accounts.charlie, 200), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.eve), 10);
+			assert_eq!(erc20.balance_of(accounts.charlie), 189);
+			assert_eq!(erc20.total_burned(), 3);
+
+			assert_eq!(erc20.burn(7), Ok(()));
+			assert_eq!(erc20.total_burned(), 10);
+			assert_eq!(erc20.total_supply(), 990);
+		}
+
+		#[ink::test]
+        fn it_staking_rewards_follow_time_weighted_stake() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new(10_000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+
+			assert_eq!(erc20.fund_rewards(1000, 0), Err(Error::InvalidRewardDuration));
+			assert_eq!(erc20.fund_rewards(1000, 10), Ok(()));
+			assert_eq!(erc20.balance_of(contract), 1000);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.stake(100), Ok(()));
+			assert_eq!(erc20.staked_of(accounts.bob), 100);
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.stake(100), Ok(()));
+
+			// Nothing is paid until the epoch is over and the whole of it can be weighed.
+			for _ in 0..7 {
+				ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			}
+			assert_eq!(erc20.pending_rewards(accounts.bob), 0);
+			assert_eq!(erc20.claim_rewards(), Err(Error::NoRewards));
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			// 1000 stake-blocks to 800.
+			assert_eq!(erc20.pending_rewards(accounts.bob), 555);
+			assert_eq!(erc20.pending_rewards(accounts.charlie), 444);
+			assert_eq!(erc20.reward_per_token(), 5_555_555_555_550);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.claim_rewards(), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 555);
+			assert_eq!(erc20.pending_rewards(accounts.bob), 0);
+			assert_eq!(erc20.unstake(101), Err(Error::InsufficientStake));
+			assert_eq!(erc20.unstake(100), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 655);
+
+			// Charlie alone earns the next epoch, on top of what is left from the first.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.fund_rewards(1000, 10), Ok(()));
+			for _ in 0..20 {
+				ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			}
+			assert_eq!(erc20.pending_rewards(accounts.bob), 0);
+			assert_eq!(erc20.pending_rewards(accounts.charlie), 1444);
+			assert_eq!(erc20.total_staked(), 100);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.claim_rewards(), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.charlie), 1444);
+			assert_eq!(erc20.fund_rewards(1, 1), Err(Error::NotOwner));
+		}
+
+		fn airdrop_leaf(index: u64, account: AccountId, amount: Balance) -> Hash {
+			let mut leaf = [0u8; 32];
+			ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&(index, account, amount), &mut leaf);
+			Hash::from(leaf)
+		}
+
+		#[ink::test]
+        fn it_airdrop_claims_verify_merkle_proofs() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			let leaves = [
+				airdrop_leaf(0, accounts.bob, 100),
+				airdrop_leaf(1, accounts.charlie, 50),
+				airdrop_leaf(200, accounts.django, 25),
+			];
+			let left = Erc20::_hash_pair(&leaves[0], &leaves[1]);
+			let root = Erc20::_hash_pair(&left, &leaves[2]);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.claim(0, 100, vec![leaves[1], leaves[2]]), Err(Error::NoMerkleRoot));
+			assert_eq!(erc20.set_merkle_root(root), Err(Error::NotOwner));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.set_merkle_root(root), Ok(()));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.claim(0, 101, vec![leaves[1], leaves[2]]), Err(Error::InvalidProof));
+			assert_eq!(erc20.claim(0, 100, vec![leaves[1], leaves[2]]), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert!(erc20.is_claimed(0));
+			assert!(!erc20.is_claimed(1));
+			assert_eq!(erc20.claim(0, 100, vec![leaves[1], leaves[2]]), Err(Error::AlreadyClaimed));
+
+			// Someone else's leaf cannot be claimed by the caller.
+			assert_eq!(erc20.claim(200, 25, vec![left]), Err(Error::InvalidProof));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.django);
+			assert_eq!(erc20.claim(200, 25, vec![left]), Ok(()));
+			assert!(erc20.is_claimed(200));
+			assert_eq!(erc20.total_supply(), 1125);
+		}
+
+		#[ink::test]
+        fn it_buy_mints_at_the_sale_rate() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(accounts.bob, 100);
+			ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(10);
+			assert_eq!(erc20.buy(), Err(Error::SaleClosed));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(0);
+			assert_eq!(erc20.set_sale_rate(5), Ok(()));
+			assert_eq!(erc20.buy(), Err(Error::ZeroPurchase));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			ink_env::test::transfer_in::<ink_env::DefaultEnvironment>(10);
+			assert_eq!(erc20.buy(), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 50);
+			assert_eq!(erc20.sale_proceeds(), 10);
+			assert_eq!(erc20.withdraw_proceeds(accounts.bob), Err(Error::NotOwner));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(accounts.eve, 0);
+			assert_eq!(erc20.withdraw_proceeds(accounts.eve), Ok(()));
+			assert_eq!(ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.eve), Ok(10));
+			assert_eq!(ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob), Ok(90));
+			assert_eq!(erc20.sale_proceeds(), 0);
+			assert_eq!(ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract), Ok(0));
+		}
+
+		#[ink::test]
+        fn it_wrapped_supply_is_backed_by_native_balance() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new_wrapped("Wrapped DOT".to_string(), "WDOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert!(erc20.is_wrapped());
+			assert_eq!(erc20.total_supply(), 0);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(accounts.bob, 100);
+			ink_env::test::transfer_in::<ink_env::DefaultEnvironment>(100);
+			assert_eq!(erc20.deposit(), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract), Ok(100));
+
+			ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(0);
+			assert_eq!(erc20.withdraw(101), Err(Error::InsufficientBalance));
+			assert_eq!(erc20.withdraw(40), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 60);
+			assert_eq!(erc20.total_supply(), 60);
+			assert_eq!(ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob), Ok(40));
+			assert_eq!(ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract), Ok(60));
+
+			// Minting without a matching deposit would leave supply unbacked.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.mint(1), Err(Error::Unbacked));
+
+			// A sale would mint against the paid value and later hand it to the owner as proceeds.
+			assert_eq!(erc20.set_sale_rate(1), Ok(()));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			ink_env::test::transfer_in::<ink_env::DefaultEnvironment>(10);
+			assert_eq!(erc20.buy(), Err(Error::SaleUnavailable));
+			ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(0);
+			ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 60);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.withdraw_proceeds(accounts.alice), Ok(()));
+			assert_eq!(ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract), Ok(60));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.withdraw(60), Ok(()));
+			assert_eq!(erc20.total_supply(), 0);
+		}
+
+		#[ink::test]
+        fn it_deposit_requires_wrapped_token() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(100);
+			assert_eq!(erc20.deposit(), Err(Error::NotWrapped));
+			assert_eq!(erc20.balance_of(accounts.bob), 0);
+			assert_eq!(erc20.withdraw(1), Err(Error::NotWrapped));
+		}
+
+		#[ink::test]
+        fn it_flash_loan_burns_principal_and_fee() {
+			let lender = set_contract_account();
+    		let mut erc20 = Erc20::new_capped(1000, "Polkadot".to_string(), "DOT".to_string(), 10, 10_000);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.set_flash_fee_bps(100), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+			assert_eq!(erc20.flash_fee(500), 5);
+			assert_eq!(erc20.max_flash_loan(), 9000);
+			assert_eq!(erc20.flash_loan(accounts.bob, 9001, vec![]), Err(Error::CapExceeded));
+
+			FLASH_LOAN_APPROVAL.with(|approval| approval.set(Some(505)));
+			assert_eq!(erc20.flash_loan(accounts.bob, 500, vec![1, 2, 3]), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 5);
+			assert_eq!(erc20.total_supply(), 995);
+			assert_eq!(erc20.allowance(accounts.bob, lender), 0);
+
+			// The failures below would revert on-chain; the off-chain env keeps their writes, so they go last.
+			FLASH_LOAN_APPROVAL.with(|approval| approval.set(Some(504)));
+			assert_eq!(erc20.flash_loan(accounts.bob, 500, vec![]), Err(Error::InsufficientAllowance));
+			FLASH_LOAN_APPROVAL.with(|approval| approval.set(None));
+			assert_eq!(erc20.flash_loan(accounts.bob, 500, vec![]), Err(Error::FlashLoanRejected));
+		}
+
+		#[ink::test]
+        fn it_transfer_with_data_respects_receiver_rejection() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			REJECTING_RECEIVERS.with(|receivers| receivers.borrow_mut().push(accounts.charlie));
+
+			assert_eq!(erc20.transfer_with_data(accounts.bob, 100, vec![1]), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(
+				erc20.transfer_with_data(accounts.charlie, 100, vec![1]),
+				Err(Error::SafeTransferCheckFailed("rejected".to_string()))
+			);
+			assert_eq!(
+				PSP22::transfer(&mut erc20, accounts.charlie, 100, vec![]),
+				Err(PSP22Error::SafeTransferCheckFailed("rejected".to_string()))
+			);
+			assert_eq!(erc20.balance_of(accounts.charlie), 0);
+			// Plain `transfer` does not consult the receiver.
+			assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+		}
+
+		#[ink::test]
+        fn it_guarded_messages_reject_reentry() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			REENTER.with(|reenter| reenter.set(true));
+
+			assert_eq!(erc20.transfer_with_data(accounts.bob, 100, vec![]), Ok(()));
+			assert_eq!(REENTRANT_RESULT.with(|result| result.take()), Some(Err(Error::ReentrancyDetected)));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+
+			FLASH_LOAN_APPROVAL.with(|approval| approval.set(Some(500)));
+			assert_eq!(erc20.flash_loan(accounts.bob, 500, vec![]), Ok(()));
+			assert_eq!(REENTRANT_RESULT.with(|result| result.take()), Some(Err(Error::ReentrancyDetected)));
+			assert_eq!(erc20.total_supply(), 1000);
+
+			// A callee re-entering while the stake is paid out finds the lock held.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.stake(100), Ok(()));
+			erc20._v2._reentrancy_lock.insert((), &true);
+			assert_eq!(erc20.unstake(100), Err(Error::ReentrancyDetected));
+			assert_eq!(erc20.claim_rewards(), Err(Error::ReentrancyDetected));
+			erc20._v2._reentrancy_lock.remove(());
+			assert_eq!(erc20.unstake(100), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+		}
+
+		#[ink::test]
+        fn it_frozen_accounts_cannot_move_tokens() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+
+			assert_eq!(erc20.freeze_account(accounts.bob), Ok(()));
+			assert!(erc20.is_frozen(accounts.bob));
+			assert_eq!(erc20.transfer(accounts.bob, 1), Err(Error::AccountFrozen));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer(accounts.charlie, 1), Err(Error::AccountFrozen));
+			assert_eq!(erc20.approve(accounts.charlie, 50), Ok(()));
+			assert_eq!(erc20.unfreeze_account(accounts.bob), Err(Error::MissingRole));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.transfer_from(accounts.bob, accounts.charlie, 50), Err(Error::AccountFrozen));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.unfreeze_account(accounts.bob), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 1), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 101);
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			// Initial mint, two transfers, freeze, approval, unfreeze.
+			assert_eq!(emitted_events.len(), 6);
+		}
+
+		#[ink::test]
+        #[cfg(feature = "pausable")]
+        fn it_detects_transfer_restrictions() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.detect_transfer_restriction(accounts.alice, accounts.bob, 10), RESTRICTION_SUCCESS);
+			assert_eq!(erc20.pause(), Ok(()));
+			assert_eq!(erc20.detect_transfer_restriction(accounts.alice, accounts.bob, 10), RESTRICTION_PAUSED);
+			assert_eq!(erc20.unpause(), Ok(()));
+			assert_eq!(erc20.freeze_account(accounts.bob), Ok(()));
+			assert_eq!(erc20.detect_transfer_restriction(accounts.alice, accounts.bob, 10), RESTRICTION_FROZEN);
+			assert_eq!(erc20.message_for_restriction_code(RESTRICTION_FROZEN), "Sender or recipient is frozen");
+			assert_eq!(erc20.unfreeze_account(accounts.bob), Ok(()));
+
+			assert_eq!(erc20.message_for_restriction_code(42), "Unknown restriction code");
+			assert_eq!(erc20.set_restriction_rules(Some(accounts.django)), Ok(()));
+			RULES_CODE.with(|code| code.set(42));
+			assert_eq!(erc20.detect_transfer_restriction(accounts.alice, accounts.bob, 10), 42);
+			assert_eq!(erc20.message_for_restriction_code(42), "Rule 42");
+			assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::TransferRestricted(42)));
+
+			RULES_CODE.with(|code| code.set(RESTRICTION_SUCCESS));
+			assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.set_restriction_rules(None), Err(Error::NotOwner));
+		}
+
+		#[ink::test]
+        fn it_allowlist_mode_gates_transfers() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert!(!erc20.allowlist_enabled());
+			assert_eq!(erc20.set_allowlist_enabled(true), Ok(()));
+			assert_eq!(erc20.add_to_allowlist(vec![accounts.alice, accounts.bob]), Ok(()));
+			assert!(erc20.is_allowlisted(accounts.bob));
+			assert_eq!(erc20.transfer(accounts.bob, 10), Ok(()));
+			assert_eq!(erc20.detect_transfer_restriction(accounts.alice, accounts.charlie, 10), RESTRICTION_NOT_ALLOWLISTED);
+
+			assert_eq!(erc20.remove_from_allowlist(vec![accounts.bob]), Ok(()));
+			assert!(!erc20.is_allowlisted(accounts.bob));
+			assert_eq!(erc20.set_allowlist_enabled(false), Ok(()));
+			assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.add_to_allowlist(vec![accounts.bob]), Err(Error::NotOwner));
+			assert_eq!(erc20.set_allowlist_enabled(true), Err(Error::NotOwner));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.set_allowlist_enabled(true), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 10), Err(Error::NotAllowlisted));
+		}
+
+		#[ink::test]
+        fn it_max_tx_and_max_wallet_limits_work() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.set_max_tx_amount(50), Ok(()));
+			assert_eq!(erc20.set_max_wallet_balance(80), Ok(()));
+			assert_eq!(erc20.active_limits().max_tx_amount, Some(50));
+			assert_eq!(erc20.active_limits().max_wallet_balance, Some(80));
+
+			// The owner is exempt from the per-transfer maximum, but bob's wallet is not.
+			assert!(erc20.is_limit_exempt(accounts.alice));
+			assert_eq!(erc20.transfer(accounts.bob, 80), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 1), Err(Error::MaxWalletExceeded));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer(accounts.charlie, 51), Err(Error::MaxTxExceeded));
+			assert_eq!(erc20.transfer(accounts.charlie, 50), Ok(()));
+			assert_eq!(erc20.set_limit_exempt(accounts.charlie, true), Err(Error::NotOwner));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.set_limit_exempt(accounts.charlie, true), Ok(()));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer(accounts.charlie, 30), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.charlie), 80);
+		}
+
+		#[ink::test]
+        fn it_launch_guard_works() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new_prelaunch(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert!(!erc20.trading_enabled());
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer(accounts.charlie, 10), Err(Error::TradingNotEnabled));
+			assert_eq!(erc20.enable_trading(), Err(Error::NotOwner));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.enable_trading(), Ok(()));
+			assert_eq!(erc20.set_cooldown_blocks(2), Ok(()));
+			assert_eq!(erc20.active_limits().cooldown_blocks, Some(2));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.transfer(accounts.charlie, 10), Err(Error::CooldownActive));
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.transfer(accounts.charlie, 10), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.charlie), 20);
+
+			// The owner is exempt from the cooldown.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.transfer(accounts.charlie, 1), Ok(()));
+			assert_eq!(erc20.transfer(accounts.charlie, 1), Ok(()));
+		}
+
+		#[ink::test]
+        fn it_admin_multisig_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.set_admin_multisig(vec![accounts.bob, accounts.bob], 1), Err(Error::InvalidMultisig));
+			assert_eq!(erc20.set_admin_multisig(vec![accounts.bob, accounts.charlie], 3), Err(Error::InvalidMultisig));
+			assert_eq!(erc20.set_admin_multisig(vec![accounts.bob, accounts.charlie, accounts.django], 2), Ok(()));
+			assert_eq!(erc20.propose_admin_action(AdminAction::Pause), Err(Error::NotAdminSigner));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			let action = AdminAction::Mint { to: accounts.eve, amount: 100 };
+			assert_eq!(erc20.propose_admin_action(action.clone()), Ok(0));
+			assert_eq!(erc20.admin_action(0), Some(action));
+			assert_eq!(erc20.approve_action(0), Err(Error::AlreadyApproved));
+			assert_eq!(erc20.execute_action(0), Err(Error::ThresholdNotReached));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.approve_action(0), Ok(()));
+			assert_eq!(erc20.admin_approvals(0), 2);
+			assert_eq!(erc20.execute_action(0), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.eve), 100);
+			assert_eq!(erc20.admin_action(0), None);
+			assert_eq!(erc20.execute_action(0), Err(Error::AdminActionNotFound));
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			// Initial mint, proposal, approval, mint, execution.
+			assert_eq!(emitted_events.len(), 5);
+			let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[4].data[..]).expect("invalid event");
+			assert!(matches!(decoded, Event::AdminActionExecuted(AdminActionExecuted { id: 0 })));
+
+			assert_eq!(erc20.propose_admin_action(AdminAction::TransferOwnership { new_owner: accounts.django }), Ok(1));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.django);
+			assert_eq!(erc20.approve_action(1), Ok(()));
+			assert_eq!(erc20.execute_action(1), Ok(()));
+			assert_eq!(erc20.pending_owner(), Some(accounts.django));
+
+			// With a timelock delay, the multisig's mints wait it out like the owner's.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.set_timelock_delay(2), Ok(()));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.propose_admin_action(AdminAction::Mint { to: accounts.eve, amount: 50 }), Ok(2));
+			assert_eq!(erc20.approve_action(2), Err(Error::AlreadyApproved));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.approve_action(2), Ok(()));
+			assert_eq!(erc20.execute_action(2), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.eve), 100);
+			assert_eq!(erc20.admin_action(2), None);
+			assert_eq!(erc20.execute_operation(0), Err(Error::OperationNotReady));
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.frank);
+			assert_eq!(erc20.execute_operation(0), Err(Error::NotAdminSigner));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.execute_operation(0), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.eve), 150);
+		}
+
+		#[ink::test]
+        fn it_timelocked_operations_work() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.set_timelock_delay(2), Ok(()));
+			assert_eq!(erc20.set_timelock_delay(1), Err(Error::TimelockDelayDecrease));
+			assert_eq!(erc20.timelock_delay(), 2);
+			assert_eq!(erc20.mint_to(accounts.bob, 100), Err(Error::TimelockRequired));
+			assert_eq!(erc20.set_fee(100, None), Err(Error::TimelockRequired));
+			assert_eq!(erc20.set_burn_rate_bps(100), Err(Error::TimelockRequired));
+			assert_eq!(erc20.set_flash_fee_bps(100), Err(Error::TimelockRequired));
+			assert_eq!(erc20.set_merkle_root(Hash::from([1u8; 32])), Err(Error::TimelockRequired));
+			assert_eq!(erc20.set_sale_rate(10), Err(Error::TimelockRequired));
+			assert_eq!(erc20.set_bonding_curve(Some((1_000, 1_000))), Err(Error::TimelockRequired));
+			assert_eq!(erc20.bridge_unlock(accounts.bob, 100, 7, 1), Err(Error::TimelockRequired));
+			assert_eq!(erc20.bridge_mint(accounts.bob, 100, 7, Hash::from([2u8; 32])), Err(Error::TimelockRequired));
+			assert_eq!(erc20.rebase(100), Err(Error::TimelockRequired));
+			assert_eq!(erc20.total_supply(), 1000);
+
+			assert_eq!(erc20.queue_operation(AdminAction::Mint { to: accounts.bob, amount: 100 }), Ok(0));
+			assert_eq!(erc20.queue_operation(AdminAction::SetFee { fee_bps: 100, collector: None }), Ok(1));
+			assert_eq!(erc20.execute_operation(0), Err(Error::OperationNotReady));
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.execute_operation(0), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.cancel_operation(1), Ok(()));
+			assert_eq!(erc20.queued_operation(1), None);
+			assert_eq!(erc20.execute_operation(1), Err(Error::OperationNotFound));
+			assert_eq!(erc20.fee_bps(), 0);
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			// Initial mint, two queued operations, mint, execution, cancellation.
+			assert_eq!(emitted_events.len(), 6);
+			let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[5].data[..]).expect("invalid event");
+			assert!(matches!(decoded, Event::OperationCancelled(OperationCancelled { id: 1 })));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.queue_operation(AdminAction::Pause), Err(Error::NotOwner));
+		}
+
+		#[ink::test]
+        fn it_upgrade_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			let code_hash = Hash::from([7u8; 32]);
+
+			assert_eq!(erc20.storage_version(), STORAGE_VERSION);
+			assert_eq!(erc20.upgrade(code_hash), Ok(()));
+			assert_eq!(UPGRADED_TO.with(|upgraded| upgraded.get()), Some(code_hash));
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..]).expect("invalid event");
+			assert!(matches!(decoded, Event::Upgraded(Upgraded { code_hash: hash }) if hash == code_hash));
+
+			assert_eq!(erc20.set_timelock_delay(1), Ok(()));
+			assert_eq!(erc20.upgrade(code_hash), Err(Error::TimelockRequired));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.upgrade(code_hash), Err(Error::NotOwner));
+		}
+
+		#[ink::test]
+        fn it_mint_limit_per_period_works() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.mint_budget_remaining(), None);
+			assert_eq!(erc20.set_mint_limit(100, 0), Err(Error::InvalidMintLimit));
+			assert_eq!(erc20.set_mint_limit(100, 2), Ok(()));
+			assert_eq!(erc20.active_limits().mint_limit_per_period, Some(100));
+
+			assert_eq!(erc20.mint_to(accounts.bob, 60), Ok(()));
+			assert_eq!(erc20.mint_budget_remaining(), Some(40));
+			assert_eq!(erc20.mintable_remaining_for(accounts.alice), 40);
+			assert_eq!(erc20.mint_to(accounts.bob, 41), Err(Error::MintLimitExceeded));
+			assert_eq!(erc20.mint_to(accounts.bob, 40), Ok(()));
+			assert_eq!(erc20.mint_budget_remaining(), Some(0));
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.mint_budget_remaining(), Some(100));
+			// A repaid flash loan leaves the budget to the minters.
+			FLASH_LOAN_APPROVAL.with(|approval| approval.set(Some(100)));
+			assert_eq!(erc20.flash_loan(accounts.bob, erc20.max_flash_loan(), vec![]), Ok(()));
+			assert_eq!(erc20.mint_budget_remaining(), Some(100));
+			assert_eq!(erc20.mint_to(accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 200);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.set_mint_limit(0, 0), Err(Error::NotOwner));
+		}
+
+		#[ink::test]
+        fn it_bridge_mint_and_burn_work() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			let src_tx = Hash::from([1u8; 32]);
+
+			assert_eq!(erc20.grant_role(BRIDGE_ROLE, accounts.eve), Ok(()));
+			assert_eq!(erc20.set_bridge_mint_cap(accounts.eve, Some(150)), Ok(()));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.eve);
+			assert_eq!(erc20.bridge_mint(accounts.bob, 100, 7, src_tx), Ok(()));
+			assert!(erc20.is_bridge_tx_used(7, src_tx));
+			assert_eq!(erc20.bridge_mint(accounts.bob, 10, 7, src_tx), Err(Error::BridgeTxUsed));
+			assert_eq!(erc20.bridge_mint(accounts.bob, 51, 7, Hash::from([2u8; 32])), Err(Error::BridgeCapExceeded));
+			assert_eq!(erc20.bridge_burn(accounts.bob, 40, 7, vec![0xab; 20]), Err(Error::InsufficientAllowance));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.approve(accounts.eve, 40), Ok(()));
+			assert_eq!(erc20.bridge_mint(accounts.bob, 10, 7, Hash::from([3u8; 32])), Err(Error::MissingRole));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.eve);
+			assert_eq!(erc20.bridge_burn(accounts.bob, 40, 7, vec![0xab; 20]), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 60);
+			assert_eq!(erc20.bridge_minted(accounts.eve), 60);
+			assert_eq!(erc20.bridge_mint(accounts.bob, 90, 7, Hash::from([2u8; 32])), Ok(()));
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			// Initial mint and role grant, then the bridged mint.
+			let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[3].data[..]).expect("invalid event");
+			match decoded {
+				Event::BridgeMinted(event) => {
+					assert_eq!(event.bridge, accounts.eve);
+					assert_eq!(event.src_chain, 7);
+					assert_eq!(event.src_tx, src_tx);
+				}
+				_ => panic!("expected a BridgeMinted event"),
+			}
+		}
+
+		#[ink::test]
+        fn it_hashlocked_swaps_work() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			let preimage = b"secret".to_vec();
+			let mut hashlock = [0u8; 32];
+			ink_env::hash_bytes::<ink_env::hash::Sha2x256>(&preimage, &mut hashlock);
+			let hashlock = Hash::from(hashlock);
+			let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.create_swap(accounts.bob, 100, hashlock, now), Err(Error::InvalidTimelock));
+			assert_eq!(erc20.create_swap(accounts.bob, 100, hashlock, now + 12), Ok(0));
+			assert_eq!(erc20.create_swap(accounts.bob, 50, hashlock, now + 12), Ok(1));
+			assert_eq!(erc20.balance_of(accounts.alice), 850);
+
+			// Anyone may claim for the recipient with the right preimage.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.claim_swap(0, b"wrong".to_vec()), Err(Error::InvalidPreimage));
+			assert_eq!(erc20.refund_swap(0), Err(Error::SwapNotExpired));
+			assert_eq!(erc20.claim_swap(0, preimage.clone()), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.hashlocked_swap(0), None);
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.claim_swap(1, preimage), Err(Error::SwapExpired));
+			assert_eq!(erc20.refund_swap(1), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 900);
+			assert_eq!(erc20.refund_swap(1), Err(Error::SwapNotFound));
+		}
+
+		#[ink::test]
+        fn it_streams_work() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.create_stream(accounts.bob, 120, now + 12, now + 12), Err(Error::InvalidStream));
+			assert_eq!(erc20.create_stream(accounts.bob, 120, now, now + 12), Ok(0));
+			assert_eq!(erc20.balance_of(accounts.alice), 880);
+
+			// Half of the stream has passed.
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.balance_of_stream(0, accounts.bob), 60);
+			assert_eq!(erc20.balance_of_stream(0, accounts.alice), 60);
+			assert_eq!(erc20.withdraw_from_stream(0, 10), Err(Error::NotStreamParty));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.withdraw_from_stream(0, 61), Err(Error::InsufficientStreamBalance));
+			assert_eq!(erc20.withdraw_from_stream(0, 40), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 40);
+			assert_eq!(erc20.balance_of_stream(0, accounts.bob), 20);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.cancel_stream(0), Err(Error::NotStreamParty));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.cancel_stream(0), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 60);
+			assert_eq!(erc20.balance_of(accounts.alice), 940);
+			assert_eq!(erc20.stream(0), None);
+		}
+
+		#[ink::test]
+        fn it_escrows_work() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.create_escrow(accounts.bob, accounts.charlie, 100), Ok(0));
+			assert_eq!(erc20.create_escrow(accounts.bob, accounts.charlie, 50), Ok(1));
+			assert_eq!(erc20.balance_of(accounts.alice), 850);
+
+			// The buyer releases the first escrow to the seller.
+			assert_eq!(erc20.refund_escrow(0), Err(Error::NotEscrowParty));
+			assert_eq!(erc20.release_escrow(0), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.escrow(0), None);
+
+			// Once the seller disputes the second, only the arbiter decides.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.dispute_escrow(1), Ok(()));
+			assert_eq!(erc20.dispute_escrow(1), Err(Error::EscrowDisputed));
+			assert_eq!(erc20.refund_escrow(1), Err(Error::NotEscrowParty));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.dispute_escrow(1), Err(Error::NotEscrowParty));
+			assert_eq!(erc20.refund_escrow(1), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 900);
+			assert_eq!(erc20.release_escrow(1), Err(Error::EscrowNotFound));
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[emitted_events.len() - 1].data[..]).expect("invalid event");
+			assert!(matches!(decoded, Event::EscrowRefunded(EscrowRefunded { id: 1, by }) if by == accounts.charlie));
+		}
+
+		#[ink::test]
+        fn it_dividends_work() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.transfer(accounts.bob, 300), Ok(()));
+			// Alice keeps 600 and Bob 300 of the 900 left in circulation.
+			assert_eq!(erc20.distribute(100), Ok(()));
+			assert_eq!(erc20.withdrawable_dividend_of(accounts.alice), 66);
+			assert_eq!(erc20.withdrawable_dividend_of(accounts.bob), 33);
+
+			// Moving tokens after a distribution leaves earned dividends where they were.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer(accounts.charlie, 150), Ok(()));
+			assert_eq!(erc20.withdrawable_dividend_of(accounts.bob), 33);
+			assert_eq!(erc20.withdrawable_dividend_of(accounts.charlie), 0);
+			assert_eq!(erc20.withdraw_dividend(), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 183);
+			assert_eq!(erc20.withdraw_dividend(), Err(Error::NoDividend));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.withdraw_dividend(), Err(Error::NoDividend));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.withdraw_dividend(), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 666);
+			assert_eq!(erc20.withdrawable_dividend_of(accounts.alice), 0);
+		}
+
+		#[ink::test]
+        fn it_rebase_scales_balances() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new_rebasing(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert!(erc20.is_rebasing());
+			assert_eq!(erc20.transfer(accounts.bob, 250), Ok(()));
+			assert_eq!(erc20.rebase(1000), Ok(()));
+			assert_eq!(erc20.total_supply(), 2000);
+			assert_eq!(erc20.balance_of(accounts.alice), 1500);
+			assert_eq!(erc20.balance_of(accounts.bob), 500);
+
+			assert_eq!(erc20.rebase(-1500), Ok(()));
+			assert_eq!(erc20.rebase_epoch(), 2);
+			assert_eq!(erc20.balance_of(accounts.alice), 375);
+			assert_eq!(erc20.balance_of(accounts.bob), 125);
+			assert_eq!(erc20.rebase(-500), Err(Error::InvalidRebase));
+
+			assert_eq!(erc20.transfer(accounts.bob, 75), Ok(()));
+			assert_eq!(erc20.mint_to(accounts.charlie, 100), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 200);
+			assert_eq!(erc20.total_supply(), 600);
+			assert_eq!(erc20.rebase(600), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 600);
+			assert_eq!(erc20.balance_of(accounts.bob), 400);
+			assert_eq!(erc20.balance_of(accounts.charlie), 200);
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[emitted_events.len() - 1].data[..]).expect("invalid event");
+			assert!(matches!(decoded, Event::Rebase(Rebase { epoch: 3, total_supply: 1200 })));
+
+			// Escrowed tokens would be rescaled under the escrow's recorded amount.
+			assert_eq!(erc20.create_escrow(accounts.bob, accounts.charlie, 300), Ok(0));
+			assert_eq!(erc20.rebase(-600), Err(Error::RebaseWithCustody));
+			assert_eq!(erc20.release_escrow(0), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 700);
+			assert_eq!(erc20.rebase(-600), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 150);
+			assert_eq!(erc20.balance_of(accounts.bob), 350);
+			assert_eq!(erc20.distribute(10), Ok(()));
+			assert_eq!(erc20.rebase(600), Err(Error::RebaseWithCustody));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.rebase(1), Err(Error::MissingRole));
+		}
+
+		#[ink::test]
+        fn it_rebase_requires_rebasing_mode() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			assert!(!erc20.is_rebasing());
+			assert_eq!(erc20.rebase(100), Err(Error::NotRebasing));
+		}
+
+		#[ink::test]
+        fn it_bonding_curve_works() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(accounts.bob, 1000);
+			assert_eq!(erc20.buy_from_curve(), Err(Error::NoCurve));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.set_bonding_curve(Some((0, 0))), Err(Error::InvalidCurve));
+			assert_eq!(erc20.set_bonding_curve(Some((1000, 0))), Err(Error::InvalidCurve));
+			assert_eq!(erc20.bonding_curve(), None);
+			assert_eq!(erc20.set_bonding_curve(Some((1000, 10_000))), Ok(()));
+			assert_eq!(erc20.spot_price(), Some(1_000_000_000));
+			assert_eq!(erc20.quote_buy(909), Some(100));
+			assert_eq!(erc20.quote_buy(10_000), None);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			ink_env::test::transfer_in::<ink_env::DefaultEnvironment>(100);
+			assert_eq!(erc20.buy_from_curve(), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 909);
+			assert_eq!(erc20.total_supply(), 1909);
+			// Buying moved the price up.
+			assert!(erc20.spot_price() > Some(1_000_000_000));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.set_bonding_curve(None), Err(Error::CurveUnavailable));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(0);
+			assert_eq!(erc20.sell_to_curve(910), Err(Error::InsufficientCurveSupply));
+			assert_eq!(erc20.sell_to_curve(909), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 0);
+			// Rounding stays with the reserve.
+			assert_eq!(ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.bob), Ok(999));
+			assert_eq!(erc20.bonding_curve().map(|curve| curve.reserve), Some(1));
+			assert_eq!(ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract), Ok(1));
+		}
+
+		#[ink::test]
+        fn it_tracks_holders() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.holder_count(), 1);
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+			assert_eq!(erc20.transfer(accounts.charlie, 100), Ok(()));
+			assert_eq!(erc20.holder_count(), 3);
+			assert_eq!(erc20.holders(1, 5), vec![(accounts.bob, 100), (accounts.charlie, 200)]);
+
+			// Emptying a balance drops the holder and the last one takes its place.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer(accounts.alice, 100), Ok(()));
+			assert_eq!(erc20.holder_count(), 2);
+			assert_eq!(erc20.holders(0, 5), vec![(accounts.alice, 800), (accounts.charlie, 200)]);
+			assert_eq!(erc20.holders(2, 5), vec![]);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.burn(800), Ok(()));
+			assert_eq!(erc20.holders(0, 5), vec![(accounts.charlie, 200)]);
+		}
+
+		#[ink::test]
+        fn it_circulating_supply_leaves_out_excluded_accounts() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.transfer(accounts.bob, 300), Ok(()));
+			assert_eq!(erc20.transfer(accounts.charlie, 200), Ok(()));
+			assert_eq!(erc20.circulating_supply(), 1000);
+
+			assert_eq!(erc20.set_excluded_from_circulation(accounts.bob, true), Ok(()));
+			assert_eq!(erc20.set_excluded_from_circulation(accounts.charlie, true), Ok(()));
+			assert_eq!(erc20.set_excluded_from_circulation(accounts.charlie, true), Ok(()));
+			assert_eq!(erc20.excluded_accounts(), vec![accounts.bob, accounts.charlie]);
+			assert_eq!(erc20.circulating_supply(), 500);
+
+			assert_eq!(erc20.set_excluded_from_circulation(accounts.bob, false), Ok(()));
+			assert_eq!(erc20.excluded_accounts(), vec![accounts.charlie]);
+			assert_eq!(erc20.circulating_supply(), 800);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.set_excluded_from_circulation(accounts.bob, true), Err(Error::NotOwner));
+		}
+
+		#[ink::test]
+        #[cfg(all(feature = "pausable", feature = "fees"))]
+        fn it_token_info_works() {
+    		let mut erc20 = Erc20::new_capped(1000, "Polkadot".to_string(), "DOT".to_string(), 10, 2000);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.set_fee(50, Some(accounts.eve)), Ok(()));
+			assert_eq!(erc20.pause(), Ok(()));
+			assert_eq!(erc20.token_info(), TokenInfo {
+				name: "Polkadot".to_string(),
+				symbol: "DOT".to_string(),
+				decimals: 10,
+				metadata_uri: String::new(),
+				total_supply: 1000,
+				cap: Some(2000),
+				owner: accounts.alice,
+				paused: true,
+				fee_bps: 50,
+				fee_collector: Some(accounts.eve),
+				burn_rate_bps: 0,
+			});
+		}
+
+		#[ink::test]
+        #[cfg(not(feature = "mutable-metadata"))]
+        fn it_name_and_symbol_are_fixed_by_default() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			assert_eq!(erc20.set_name("Kusama".to_string()), Err(Error::MetadataImmutable));
+			assert_eq!(erc20.set_symbol("KSM".to_string()), Err(Error::MetadataImmutable));
+			assert_eq!(erc20.name(), "Polkadot");
+		}
+
+		#[ink::test]
+        #[cfg(feature = "mutable-metadata")]
+        fn it_set_name_and_symbol_work() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.set_name(" Kusama".to_string()), Err(Error::InvalidName));
+			assert_eq!(erc20.set_name("a".repeat(MAX_NAME_LEN + 1)), Err(Error::InvalidName));
+			assert_eq!(erc20.set_symbol("KS M".to_string()), Err(Error::InvalidSymbol));
+			assert_eq!(erc20.set_name("Kusama".to_string()), Ok(()));
+			assert_eq!(erc20.set_symbol("KSM".to_string()), Ok(()));
+			assert_eq!(erc20.token_name(), Some("Kusama".to_string()));
+			assert_eq!(erc20.token_symbol(), Some("KSM".to_string()));
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..]).expect("invalid event");
+			match decoded {
+				Event::MetadataUpdated(event) => {
+					assert_eq!(event.name, "Kusama");
+					assert_eq!(event.symbol, "KSM");
+				}
+				_ => panic!("expected a MetadataUpdated event"),
+			}
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.set_name("Mine".to_string()), Err(Error::NotOwner));
+		}
+
+		#[ink::test]
+        fn it_set_token_uri_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.set_token_uri("ipfs://token.json".to_string()), Ok(()));
+			assert_eq!(erc20.token_uri(), "ipfs://token.json");
+			assert_eq!(erc20.metadata_uri(), "ipfs://token.json");
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			// Initial mint, then `MetadataUpdated` and `TokenUriUpdated`.
+			assert_eq!(emitted_events.len(), 3);
+			let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..]).expect("invalid event");
+			assert!(matches!(decoded, Event::TokenUriUpdated(TokenUriUpdated { token_uri }) if token_uri == "ipfs://token.json"));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.set_token_uri("ipfs://mine.json".to_string()), Err(Error::NotOwner));
+		}
+
+		#[ink::test]
+        fn it_operators_can_transfer_from_without_allowance() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.set_operator(accounts.bob, true), Ok(()));
+			assert!(erc20.is_operator(accounts.alice, accounts.bob));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer_from(accounts.alice, accounts.charlie, 600), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.charlie), 600);
+			assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.set_operator(accounts.bob, false), Ok(()));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer_from(accounts.alice, accounts.charlie, 1), Err(Error::InsufficientAllowance));
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[1].data[..]).expect("invalid event");
+			assert!(matches!(decoded, Event::OperatorSet(OperatorSet { approved: true, .. })));
+		}
+
+		#[ink::test]
+        fn it_allowances_expire() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.approve_with_expiry(accounts.bob, 100, now), Err(Error::InvalidExpiry));
+			assert_eq!(erc20.approve_with_expiry(accounts.bob, 100, now + 6), Ok(()));
+			assert_eq!(erc20.approve(accounts.charlie, 50), Ok(()));
+			assert_eq!(erc20.live_approvals(accounts.alice), vec![
+				(accounts.bob, 100, Some(now + 6)),
+				(accounts.charlie, 50, None),
+			]);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 40), Ok(()));
+			assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 60);
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 0);
+			assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 10), Err(Error::AllowanceExpired));
+			assert_eq!(erc20.live_approvals(accounts.alice), vec![(accounts.charlie, 50, None)]);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.increase_allowance(accounts.bob, 5), Ok(()));
+			assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 5);
+
+			// A plain approval replaces the expiring one.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.approve(accounts.bob, 10), Ok(()));
+			assert_eq!(erc20.allowance_expiry(accounts.alice, accounts.bob), None);
+			assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 10);
+			assert_eq!(erc20.approve(accounts.charlie, 0), Ok(()));
+			assert_eq!(erc20.live_approvals(accounts.alice), vec![(accounts.bob, 10, None)]);
+
+			// Removing a spender moves the last one into its slot.
+			assert_eq!(erc20.approve(accounts.charlie, 30), Ok(()));
+			assert_eq!(erc20.approve(accounts.django, 20), Ok(()));
+			assert_eq!(erc20.approve(accounts.bob, 0), Ok(()));
+			assert_eq!(erc20.live_approvals(accounts.alice), vec![(accounts.django, 20, None), (accounts.charlie, 30, None)]);
+			assert_eq!(erc20.approve(accounts.charlie, 0), Ok(()));
+			assert_eq!(erc20.approve(accounts.django, 0), Ok(()));
+			assert_eq!(erc20.live_approvals(accounts.alice), vec![]);
+		}
+
+		#[ink::test]
+        fn it_recurring_allowances_reset_every_period() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.approve_recurring(accounts.bob, 100, 0), Err(Error::InvalidRecurringAllowance));
+			assert_eq!(erc20.approve_recurring(accounts.bob, 100, 2), Ok(()));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 70), Ok(()));
+			assert_eq!(erc20.recurring_available(accounts.alice, accounts.bob), 30);
+			assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 31), Err(Error::InsufficientAllowance));
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.recurring_available(accounts.alice, accounts.bob), 30);
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.recurring_available(accounts.alice, accounts.bob), 100);
+			assert_eq!(erc20.transfer_from(accounts.alice, accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 170);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.approve_recurring(accounts.bob, 0, 0), Ok(()));
+			assert_eq!(erc20.recurring_allowance(accounts.alice, accounts.bob), None);
+		}
+
+		#[ink::test]
+        fn it_rescue_leaves_reserved_funds_alone() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.set_sale_rate(1), Ok(()));
+			ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 0);
+			ink_env::test::transfer_in::<ink_env::DefaultEnvironment>(30);
+			assert_eq!(erc20.buy(), Ok(()));
+			ink_env::test::set_value_transferred::<ink_env::DefaultEnvironment>(0);
+			// Dust sent straight to the contract, outside of `buy`.
+			ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 50);
+			assert_eq!(erc20.rescuable_native(), 20);
+
+			ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(accounts.eve, 0);
+			assert_eq!(erc20.rescue_native(accounts.eve, 21), Err(Error::RescueExceedsSurplus));
+			assert_eq!(erc20.rescue_native(accounts.eve, 20), Ok(()));
+			assert_eq!(ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.eve), Ok(20));
+			assert_eq!(erc20.sale_proceeds(), 30);
+
+			assert_eq!(erc20.rescue_psp22(contract, accounts.eve, 1), Err(Error::CannotRescueOwnToken));
+			assert_eq!(erc20.rescue_psp22(accounts.django, accounts.eve, 7), Ok(()));
+			assert_eq!(PSP22_TRANSFERS.with(|transfers| transfers.borrow().clone()), vec![(accounts.django, accounts.eve, 7)]);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.rescue_native(accounts.bob, 1), Err(Error::NotOwner));
+			assert_eq!(erc20.rescue_psp22(accounts.django, accounts.bob, 1), Err(Error::NotOwner));
+		}
+
+		#[ink::test]
+        fn it_terminate_waits_for_outstanding_commitments() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.stake(10), Ok(()));
+			assert_eq!(erc20.terminate(), Err(Error::OutstandingCommitments));
+			assert_eq!(erc20.unstake(10), Ok(()));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.terminate(), Err(Error::NotOwner));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract, 100);
+			ink_env::test::assert_contract_termination::<ink_env::DefaultEnvironment, _>(
+				move || {
+					let _ = erc20.terminate();
+				},
+				accounts.alice,
+				100,
+			);
+		}
+
+		#[ink::test]
+        fn it_psp22_calls_encode_the_trait_messages() {
+			use crate::psp22_calls;
+			use ink_env::call::FromAccountId;
+			use ink_lang::ToAccountId;
+			use scale::Encode;
+
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			let mut expected = ink_lang::selector_bytes!("PSP22::balance_of").to_vec();
+			expected.extend(accounts.bob.encode());
+			assert_eq!(psp22_calls::balance_of_input(accounts.bob).encode(), expected);
+
+			let mut expected = ink_lang::selector_bytes!("PSP22::transfer_from").to_vec();
+			expected.extend((accounts.bob, accounts.charlie, 5 as Balance, vec![1u8]).encode());
+			assert_eq!(psp22_calls::transfer_from_input(accounts.bob, accounts.charlie, 5, vec![1]).encode(), expected);
+
+			let mut expected = ink_lang::selector_bytes!("PSP22::transfer").to_vec();
+			expected.extend((accounts.charlie, 5 as Balance, Vec::<u8>::new()).encode());
+			assert_eq!(psp22_calls::transfer_input(accounts.charlie, 5, Vec::new()).encode(), expected);
+
+			let token = <crate::Erc20Ref as FromAccountId<ink_env::DefaultEnvironment>>::from_account_id(accounts.django);
+			assert_eq!(token.to_account_id(), accounts.django);
+		}
+
+		#[ink::test]
+        fn it_new_for_hands_supply_and_ownership_to_owner() {
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+    		let erc20 = Erc20::new_for(accounts.bob, 1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+
+			assert_eq!(erc20.owner(), accounts.bob);
+			assert_eq!(erc20.balance_of(accounts.bob), 1000);
+			assert_eq!(erc20.balance_of(accounts.alice), 0);
+			assert!(erc20.has_role(MINTER_ROLE, accounts.bob));
+		}
+
+		#[ink::test]
+        fn it_multicall_runs_message_payloads_in_order() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			fn payload(selector: [u8; 4], args: impl scale::Encode) -> Vec<u8> {
+				let mut payload = selector.to_vec();
+				payload.extend(args.encode());
+				payload
+			}
+
+			let transfer = payload(ink_lang::selector_bytes!("transfer"), (accounts.bob, 100 as Balance));
+			let approve = payload(ink_lang::selector_bytes!("approve"), (accounts.charlie, 50 as Balance));
+			let stake = payload(ink_lang::selector_bytes!("stake"), 200 as Balance);
+			let ok = scale::Encode::encode(&Ok::<(), Error>(()));
+			assert_eq!(erc20.multicall(vec![transfer.clone(), approve, stake]), Ok(vec![ok.clone(), ok.clone(), ok]));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.allowance(accounts.alice, accounts.charlie), 50);
+			assert_eq!(erc20.staked_of(accounts.alice), 200);
+
+			let overdraw = payload(ink_lang::selector_bytes!("transfer"), (accounts.bob, 10_000 as Balance));
+			assert_eq!(erc20.multicall(vec![overdraw]), Err(Error::InsufficientBalance));
+			let mint = payload(ink_lang::selector_bytes!("mint"), 1 as Balance);
+			assert_eq!(erc20.multicall(vec![transfer.clone(), mint]), Err(Error::UnsupportedCall));
+			assert_eq!(erc20.multicall(vec![transfer[..transfer.len() - 1].to_vec()]), Err(Error::InvalidCallData));
+			assert_eq!(erc20.multicall(vec![vec![0, 1]]), Err(Error::InvalidCallData));
+			assert_eq!(erc20.multicall(vec![transfer; MAX_MULTICALL_CALLS + 1]), Err(Error::TooManyCalls));
+			// Nothing ran for batches that failed to decode.
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+		}
+
+		#[ink::test]
+        #[cfg(all(feature = "pausable", feature = "snapshots", feature = "votes", feature = "fees"))]
+        fn it_hooks_track_mints_burns_and_transfers_alike() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.delegate(accounts.alice), Ok(()));
+			let snapshot_id = erc20.snapshot().unwrap();
+			assert_eq!(erc20.mint(100), Ok(()));
+			assert_eq!(erc20.burn(50), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 250), Ok(()));
+
+			assert_eq!(erc20.get_votes(accounts.alice), 800);
+			assert_eq!(erc20.balance_of_at(accounts.alice, snapshot_id), Ok(1000));
+			assert_eq!(erc20.balance_of_at(accounts.bob, snapshot_id), Ok(0));
+			assert_eq!(erc20.total_supply_at(snapshot_id), Ok(1000));
+
+			// The fee leg runs through the hooks like any other move, so the collector becomes a holder.
+			assert_eq!(erc20.set_fee(1000, Some(accounts.eve)), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 200), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 430);
+			assert_eq!(erc20.balance_of(accounts.eve), 20);
+			assert_eq!(erc20.get_votes(accounts.alice), 600);
+			assert_eq!(erc20.holder_count(), 3);
+
+			// 50 over the 1000 held outside the contract: 1 per 20 tokens, and moving tokens later moves none of it.
+			assert_eq!(erc20.distribute(50), Ok(()));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer(accounts.charlie, 200), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.charlie), 180);
+			assert_eq!(erc20.balance_of(accounts.eve), 40);
+			assert_eq!(erc20.withdrawable_dividend_of(accounts.alice), 27);
+			assert_eq!(erc20.withdrawable_dividend_of(accounts.bob), 21);
+			assert_eq!(erc20.withdrawable_dividend_of(accounts.eve), 1);
+			assert_eq!(erc20.withdrawable_dividend_of(accounts.charlie), 0);
+			assert_eq!(erc20.holder_count(), 5);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+
+			// A mint the hooks refuse leaves votes and history untouched.
+			assert_eq!(erc20.pause(), Ok(()));
+			assert_eq!(erc20.mint(100), Err(Error::Paused));
+			assert_eq!(erc20.get_votes(accounts.alice), 550);
+			assert_eq!(erc20.total_supply(), 1050);
+		}
+
+		#[ink::test]
+        #[cfg(not(all(feature = "pausable", feature = "snapshots", feature = "votes", feature = "fees")))]
+        fn it_disabled_extensions_refuse_configuration() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			if !cfg!(feature = "pausable") {
+				assert_eq!(erc20.pause(), Err(Error::ExtensionDisabled));
+			}
+			if !cfg!(feature = "snapshots") {
+				assert_eq!(erc20.snapshot(), Err(Error::ExtensionDisabled));
+				assert_eq!(erc20.balance_of_at(accounts.alice, 1), Err(Error::SnapshotNotFound));
+			}
+			if !cfg!(feature = "votes") {
+				assert_eq!(erc20.delegate(accounts.alice), Err(Error::ExtensionDisabled));
+				assert_eq!(erc20.get_votes(accounts.alice), 0);
+			}
+			if !cfg!(feature = "fees") {
+				assert_eq!(erc20.set_fee(100, Some(accounts.eve)), Err(Error::ExtensionDisabled));
+				assert_eq!(erc20.set_burn_rate_bps(100), Err(Error::ExtensionDisabled));
+			}
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+		}
+
+		#[ink::test]
+        fn it_mint_to_and_burn_from_work() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.mint_to(accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.total_supply(), 1100);
+
+			assert_eq!(erc20.burn_from(accounts.bob, 10), Err(Error::InsufficientAllowance));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.mint_to(accounts.bob, 1), Err(Error::MissingRole));
+			assert_eq!(erc20.approve(accounts.alice, 30), Ok(()));
+			assert_eq!(erc20.burn_from(accounts.alice, 1), Err(Error::MissingRole));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.burn_from(accounts.bob, 31), Err(Error::InsufficientAllowance));
+			assert_eq!(erc20.burn_from(accounts.bob, 30), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 70);
+			assert_eq!(erc20.allowance(accounts.bob, accounts.alice), 0);
+			assert_eq!(erc20.total_supply(), 1070);
+		}
+
+		#[ink::test]
+        fn it_burn_from_batch_is_all_or_nothing() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.mint_to(accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.mint_to(accounts.charlie, 100), Ok(()));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.approve(accounts.alice, 50), Ok(()));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.approve(accounts.alice, 20), Ok(()));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.burn_from_batch(vec![accounts.bob, accounts.charlie], vec![10]), Err(Error::BatchLengthMismatch));
+			assert_eq!(
+				erc20.burn_from_batch(vec![accounts.bob, accounts.charlie], vec![30, 30]),
+				Err(Error::InsufficientAllowance)
+			);
+			// Bob's two entries add up to more than his allowance.
+			assert_eq!(
+				erc20.burn_from_batch(vec![accounts.bob, accounts.charlie, accounts.bob], vec![30, 20, 30]),
+				Err(Error::InsufficientAllowance)
+			);
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.total_supply(), 1200);
+
+			assert_eq!(erc20.burn_from_batch(vec![accounts.bob, accounts.charlie, accounts.bob], vec![30, 20, 20]), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 50);
+			assert_eq!(erc20.balance_of(accounts.charlie), 80);
+			assert_eq!(erc20.allowance(accounts.bob, accounts.alice), 0);
+			assert_eq!(erc20.allowance(accounts.charlie, accounts.alice), 0);
+			assert_eq!(erc20.total_supply(), 1130);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.burn_from_batch(Vec::new(), Vec::new()), Err(Error::MissingRole));
+		}
+
+		#[ink::test]
+        fn it_balance_math_is_checked_at_the_boundary() {
+    		let mut erc20 = Erc20::new(Balance::MAX - 1, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.mint(2), Err(Error::ArithmeticOverflow));
+			assert_eq!(erc20.total_supply(), Balance::MAX - 1);
+			assert_eq!(erc20.mint(1), Ok(()));
+			assert_eq!(erc20.total_supply(), Balance::MAX);
+			assert_eq!(erc20.mint(1), Err(Error::ArithmeticOverflow));
+
+			assert_eq!(erc20.transfer(accounts.alice, Balance::MAX), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), Balance::MAX);
+			assert_eq!(erc20.transfer(accounts.bob, Balance::MAX), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), Balance::MAX);
+			assert_eq!(erc20.transfer(accounts.bob, 1), Err(Error::InsufficientBalance));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.burn(Balance::MAX), Err(Error::MissingRole));
+			assert_eq!(erc20.transfer(accounts.alice, Balance::MAX), Ok(()));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.burn(Balance::MAX), Ok(()));
+			assert_eq!(erc20.total_supply(), 0);
+		}
+
+		#[ink::test]
+        fn it_mint_respects_cap() {
+    		let mut erc20 = Erc20::new_capped(1000, "Polkadot".to_string(), "DOT".to_string(), 10, 1100);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.cap(), Some(1100));
+			assert_eq!(erc20.mintable_remaining(), 100);
+
+			assert_eq!(erc20.mint(101), Err(Error::CapExceeded));
+			assert_eq!(erc20.total_supply(), 1000);
+			assert_eq!(erc20.mint(100), Ok(()));
+			assert_eq!(erc20.mintable_remaining_for(accounts.alice), 0);
+			assert_eq!(erc20.mint(1), Err(Error::CapExceeded));
+
+			assert_eq!(erc20.burn(10), Ok(()));
+			assert_eq!(erc20.mint(10), Ok(()));
+			assert_eq!(erc20.total_supply(), 1100);
+		}
 
-	#[ink(event)]
-	pub struct Transferred {
-		from: Option<AccountId>,
-		to: Option<AccountId>,
-		value: Balance,
-	}
+		#[ink::test]
+        fn it_mintable_remaining_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
 
-	#[ink(storage)]
-	#[derive(SpreadAllocate)]
-    pub struct Erc20 {
-		_owner: AccountId,
-        _balances: Mapping<AccountId, Balance>,
-		_total_supply: Balance,
-		_name: String,
-		_symbol: String,
-    }
+			assert_eq!(erc20.mintable_remaining(), Balance::MAX - 1000);
+			assert_eq!(erc20.mintable_remaining_for(accounts.alice), Balance::MAX - 1000);
+			assert_eq!(erc20.mintable_remaining_for(accounts.bob), 0);
 
-    impl Erc20 {
-        #[ink(constructor)]
-        pub fn new(total_supply: Balance, name: String, symbol: String) -> Self {
-			let owner = Self::env().caller();
+			assert_eq!(erc20.set_max_mint_per_tx(50), Ok(()));
+			assert_eq!(erc20.mintable_remaining_for(accounts.alice), 50);
+		}
 
-			ink_lang::utils::initialize_contract(|contract: &mut Self| {
-                contract._balances.insert(&owner, &total_supply);
-				contract._owner = owner.clone();
-				contract._total_supply = total_supply;
-				contract._name = name;
-				contract._symbol = symbol;
+		#[ink::test]
+        fn it_cancel_swap_offer_refunds_maker() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
 
-				Self::env().emit_event(Transferred {
-					from: None,
-					to: Some(owner.clone()),
-					value: total_supply,
-				});
-            })
+			let id = erc20.create_swap_offer(accounts.bob, 100, accounts.frank, 5).unwrap();
+			assert_eq!(erc20.balance_of(accounts.alice), 900);
+			assert_eq!(erc20.balance_of(contract), 100);
+			assert_eq!(erc20.swap_offer(id).unwrap().want_amount, 5);
 
-        }
+			assert_eq!(erc20.cancel_swap_offer(id), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 1000);
+			assert_eq!(erc20.balance_of(contract), 0);
+			assert_eq!(erc20.swap_offer(id), None);
+		}
 
-        #[ink(message)]
-        pub fn name(&self) -> String {
-            self._name.clone()
-        }
+		#[ink::test]
+        fn it_accept_swap_offer_rejects_wrong_counterparty() {
+			set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
 
-        #[ink(message)]
-        pub fn symbol(&self) -> String {
-            self._symbol.clone()
-        }
+			let id = erc20.create_swap_offer(accounts.bob, 100, accounts.frank, 5).unwrap();
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.accept_swap_offer(id), Err(Error::NotSwapCounterparty));
+		}
 
-        #[ink(message)]
-        pub fn total_supply(&self) -> Balance {
-            self._total_supply
-        }
+		#[ink::test]
+        fn it_large_transfers_are_delayed() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.set_large_transfer_limit(100, 60), Ok(()));
 
-        #[ink(message)]
-        pub fn balance_of(&self, account: AccountId) -> Balance {
-            self._balances.get(account).unwrap_or(0)
-        }
+			// Transfers up to the threshold go through at once.
+			assert_eq!(erc20.transfer_delayed(accounts.bob, 100), Ok(None));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.delayed_transfer(0), None);
 
-        #[ink(message)]
-        pub fn transfer(&mut self, to: AccountId, amount: Balance) -> bool {
-			let owner = self.env().caller();
-			self._transfer(&owner, &to, amount);
+			assert_eq!(erc20.transfer_delayed(accounts.bob, 500), Ok(Some(0)));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.balance_of(contract), 500);
+			assert_eq!(erc20.delayed_transfer(0).unwrap().executable_at, 60);
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.execute_delayed_transfer(0), Err(Error::DelayedTransferNotReady));
 
-			true
-        }
+			// The sender can take a pending transfer back.
+			assert_eq!(erc20.transfer_delayed(accounts.bob, 200), Ok(Some(1)));
+			assert_eq!(erc20.balance_of(accounts.alice), 200);
+			assert_eq!(erc20.cancel_delayed_transfer(1), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 400);
 
-        #[ink(message)]
-        pub fn mint(&mut self, amount: Balance) -> bool {
-			let owner = self.env().caller();
-			self.only_allowed_caller();
-			self._mint(&owner, amount);
+			for _ in 0..9 {
+				ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			}
+			assert_eq!(erc20.execute_delayed_transfer(0), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 600);
+			assert_eq!(erc20.balance_of(contract), 0);
+		}
 
-			true
-        }
+		/// `Erc20`'s fields as storage version 1 laid them out.
+		#[derive(SpreadLayout, SpreadAllocate)]
+		struct StorageV1 {
+			_owner: AccountId,
+			_balances: Mapping<AccountId, Balance>,
+			_total_supply: Balance,
+			_name: String,
+			_symbol: String,
+			_twab_start: Timestamp,
+			_twab_accumulators: Mapping<AccountId, (Balance, Timestamp)>,
+			_trusted_forwarder: Option<AccountId>,
+			_metadata_uri: String,
+			_max_mint_per_tx: Balance,
+			_swap_offers: Mapping<u64, SwapOffer>,
+			_next_swap_offer_id: u64,
+			_large_transfer_threshold: Balance,
+			_large_transfer_delay: Timestamp,
+			_delayed_transfers: Mapping<u64, DelayedTransfer>,
+			_next_delayed_transfer_id: u64,
+			_version: u32,
+		}
 
-        #[ink(message)]
-        pub fn burn(&mut self, amount: Balance) -> bool {
-			let owner = self.env().caller();
-			self.only_allowed_caller();
-			self._burn(&owner, amount);
+		#[ink::test]
+        #[cfg(feature = "fees")]
+        fn it_queued_transfers_are_charged_once_allowed() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.set_fee(MAX_FEE_BPS, Some(accounts.eve)), Ok(()));
+			assert_eq!(erc20.set_large_transfer_limit(100, 60), Ok(()));
 
-			true
-        }
+			assert_eq!(erc20.freeze_account(accounts.bob), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 500), Err(Error::AccountFrozen));
+			assert_eq!(erc20.balance_of(accounts.eve), 0);
+			assert_eq!(erc20.balance_of(accounts.alice), 1000);
 
-		fn _transfer(&mut self, from: &AccountId, to: &AccountId, amount: Balance) {
-			let from_balance = self._balances.get(from).unwrap_or(0);
-			let to_balance = self._balances.get(to).unwrap_or(0);
-			assert!(from_balance >= amount, "ERC20: transfer amount exceeds balance");
+			assert_eq!(erc20.unfreeze_account(accounts.bob), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 500), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.eve), 50);
+			assert_eq!(erc20.balance_of(contract), 450);
+			assert_eq!(erc20.delayed_transfer(0).map(|delayed| delayed.amount), Some(450));
+		}
 
-			let new_from_balance: Balance = from_balance - amount;
-			let new_to_balance: Balance = to_balance + amount;
-			self._balances.insert(from, &new_from_balance);
-			self._balances.insert(to, &new_to_balance);
+		#[ink::test]
+        fn it_migrates_version_1_storage() {
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			let root_key = ink_primitives::Key::from([0x00; 32]);
+			let mut legacy: StorageV1 = ink_storage::traits::allocate_spread_root(&root_key);
+			legacy._owner = accounts.alice;
+			legacy._balances.insert(accounts.alice, &900);
+			legacy._balances.insert(accounts.bob, &100);
+			legacy._total_supply = 1000;
+			legacy._name = "Polkadot".to_string();
+			legacy._symbol = "DOT".to_string();
+			legacy._version = 1;
+			ink_storage::traits::push_spread_root(&legacy, &root_key);
 
-			Self::env().emit_event(Transferred {
-				from: Some(from.clone()),
-				to: Some(to.clone()),
-				value: amount,
-			});
+			let mut erc20: Erc20 = ink_storage::traits::pull_spread_root(&root_key);
+			assert!(erc20.needs_migration());
+			assert_eq!(erc20.storage_version(), 1);
+			assert_eq!(erc20.name(), "Polkadot");
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.transfer(accounts.bob, 1), Err(Error::MigrationPending));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.migrate(), Err(Error::NotOwner));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.migrate(), Ok(()));
+			assert_eq!(erc20.migrate(), Err(Error::AlreadyMigrated));
+			ink_storage::traits::push_spread_root(&erc20, &root_key);
+
+			let mut erc20: Erc20 = ink_storage::traits::pull_spread_root(&root_key);
+			assert_eq!(erc20.storage_version(), STORAGE_VERSION);
+			assert!(erc20.has_role(MINTER_ROLE, accounts.alice));
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.mint(50), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 850);
+			assert_eq!(erc20.balance_of(accounts.bob), 200);
+			assert_eq!(erc20.holder_count(), 2);
 		}
 
-		fn _mint(&mut self, account: &AccountId, amount: Balance) {
-			self._total_supply += amount;
+		#[ink::test]
+        fn it_burns_can_be_redirected() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.burn_with_option(10, true), Err(Error::NoBurnRedirect));
+			assert_eq!(erc20.set_burn_redirect(Some(accounts.eve)), Ok(()));
 
-			let account_balance = self._balances.get(account).unwrap_or(0);
-			self._balances.insert(account, &(account_balance + amount));
+			assert_eq!(erc20.burn_with_option(10, false), Ok(()));
+			assert_eq!(erc20.total_supply(), 990);
+			assert_eq!(erc20.balance_of(accounts.eve), 0);
 
-			Self::env().emit_event(Transferred {
-				from: None,
-				to: Some(account.clone()),
-				value: amount,
-			});
+			assert_eq!(erc20.burn_with_option(10, true), Ok(()));
+			assert_eq!(erc20.total_supply(), 990);
+			assert_eq!(erc20.balance_of(accounts.eve), 10);
+			assert_eq!(erc20.balance_of(accounts.alice), 980);
 		}
 
-		fn _burn(&mut self, account: &AccountId, amount: Balance) {
-			let balance = self._balances.get(account).unwrap_or(0);
-			assert!(balance >= amount, "ERC20: burn amount exceeds balance");
-			self._total_supply -= amount;
-			self._balances.insert(account, &(balance - amount));
+		#[ink::test]
+        fn it_balance_root_proofs_work() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.transfer(accounts.bob, 200), Ok(()));
+			assert_eq!(erc20.transfer(accounts.charlie, 300), Ok(()));
 
-			Self::env().emit_event(Transferred {
-				from: Some(account.clone()),
-				to: None,
-				value: amount,
-			});
+			let root = erc20.compute_balance_root(vec![accounts.alice, accounts.bob, accounts.charlie]).unwrap();
+			assert_eq!(erc20.balance_root(), (root, 0));
+
+			let alice_leaf = Erc20::_balance_leaf(&accounts.alice, 500);
+			let bob_leaf = Erc20::_balance_leaf(&accounts.bob, 200);
+			let charlie_leaf = Erc20::_balance_leaf(&accounts.charlie, 300);
+			let alice_bob = Erc20::_hash_pair(&alice_leaf, &bob_leaf);
+
+			assert!(erc20.verify_balance_proof(accounts.bob, 200, vec![alice_leaf, charlie_leaf]));
+			assert!(erc20.verify_balance_proof(accounts.charlie, 300, vec![alice_bob]));
+			assert!(!erc20.verify_balance_proof(accounts.bob, 201, vec![alice_leaf, charlie_leaf]));
+			assert!(!erc20.verify_balance_proof(accounts.charlie, 300, vec![alice_leaf]));
 		}
 
-		fn only_allowed_caller(&self) {
-            assert!(
-                self._owner == self.env().caller(),
-                "only_allowed_caller: this caller is not allowed",
-            );
-        }
-    }
+		#[ink::test]
+        fn it_balance_root_rejects_unordered_or_incomplete_holders() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.transfer(accounts.bob, 200), Ok(()));
 
-    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
-    /// module and test functions are marked with a `#[test]` attribute.
-    /// The below code is technically just normal Rust code.
-    #[cfg(test)]
-    mod tests {
-        /// Imports all the definitions from the outer scope so we can use them here.
-        use super::*;
+			assert_eq!(erc20.compute_balance_root(vec![accounts.bob, accounts.alice]), Err(Error::HoldersNotOrdered));
+			assert_eq!(erc20.compute_balance_root(vec![accounts.alice]), Err(Error::HoldersIncomplete));
+			assert_eq!(erc20.compute_balance_root(vec![accounts.alice, accounts.charlie]), Err(Error::HoldersIncomplete));
+			assert_eq!(erc20.balance_root(), (Hash::default(), 0));
+			assert!(erc20.compute_balance_root(vec![accounts.alice, accounts.bob]).is_ok());
+		}
 
-        /// Imports `ink_lang` so we can use `#[ink::test]`.
-        use ink_lang as ink;
+		#[ink::test]
+        fn it_recovers_from_zero_supply() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
 
-        /// We test a simple use case of our contract.
-        #[ink::test]
-        fn it_constructor_works() {
-            let erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string());
-            assert_eq!(erc20.name(), "Polkadot");
-            assert_eq!(erc20.symbol(), "DOT");
-            assert_eq!(erc20.total_supply(), 1000);
-        }
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			assert_eq!(erc20.burn(1000), Ok(()));
+			assert_eq!(erc20.total_supply(), 0);
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.transfer(accounts.bob, 0), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 0);
+			assert_eq!(erc20.twab(accounts.alice), 500);
+
+			assert_eq!(erc20.mint(10), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 4), Ok(()));
+			assert_eq!(erc20.total_supply(), 10);
+			assert_eq!(erc20.balance_of(accounts.alice), 6);
+			assert_eq!(erc20.balance_of(accounts.bob), 4);
+		}
 
 		#[ink::test]
-        fn it_transfer_works() {
-    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string());
+        fn it_batch_queries_work() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
 			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
 
-			let bob_balance = erc20.balance_of(accounts.bob);
+			assert_eq!(erc20.transfer(accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.approve(accounts.charlie, 30), Ok(()));
 
-			assert_eq!(bob_balance, 0);
+			assert_eq!(
+				erc20.balance_of_batch(vec![accounts.alice, accounts.bob, accounts.charlie]),
+				vec![900, 100, 0]
+			);
+			assert_eq!(
+				erc20.allowance_batch(vec![(accounts.alice, accounts.charlie), (accounts.charlie, accounts.alice)]),
+				vec![30, 0]
+			);
+			assert_eq!(erc20.balance_of_batch(Vec::new()), Vec::<Balance>::new());
+		}
+
+		#[ink::test]
+        fn it_transfer_batch_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
 
-            assert_eq!(erc20.transfer(accounts.bob, 1), true);
+			assert_eq!(
+				erc20.transfer_batch(vec![(accounts.bob, 600), (accounts.charlie, 401)]),
+				Err(Error::InsufficientBalance)
+			);
+			assert_eq!(
+				erc20.transfer_batch(vec![(accounts.bob, Balance::MAX), (accounts.charlie, 1)]),
+				Err(Error::ArithmeticOverflow)
+			);
+			assert_eq!(erc20.balance_of(accounts.bob), 0);
 
-            assert_eq!(erc20.balance_of(accounts.bob), 1);
+			assert_eq!(
+				erc20.transfer_batch(vec![(accounts.bob, 600), (accounts.charlie, 300), (accounts.bob, 100)]),
+				Ok(())
+			);
+			assert_eq!(erc20.balance_of(accounts.alice), 0);
+			assert_eq!(erc20.balance_of(accounts.bob), 700);
+			assert_eq!(erc20.balance_of(accounts.charlie), 300);
 
-            // ink_env::debug_println!("balance 1 {:?} - {:?}", bob_balance, erc20.balance_of(accounts.bob));
 			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events.len(), 2);
+            assert_eq!(emitted_events.len(), 4);
 		}
 
 		#[ink::test]
-        fn it_mint_works() {
-    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string());
+        fn it_mint_and_burn_emit_dedicated_events() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
 			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
 
-			let alice_balance = erc20.balance_of(accounts.alice);
+			assert_eq!(erc20.mint(10), Ok(()));
+			assert_eq!(erc20.transfer(accounts.bob, 5), Ok(()));
+			assert_eq!(erc20.burn(3), Ok(()));
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 4);
+			// Signature topic plus `from` and `to`.
+			assert_eq!(emitted_events[2].topics.len(), 3);
+			let decoded = emitted_events
+				.iter()
+				.map(|event| <Event as scale::Decode>::decode(&mut &event.data[..]).unwrap())
+				.collect::<Vec<_>>();
+			match (&decoded[0], &decoded[1], &decoded[2], &decoded[3]) {
+				(Event::Minted(initial), Event::Minted(minted), Event::Transferred(transferred), Event::Burned(burned)) => {
+					assert_eq!((initial.to, initial.value), (accounts.alice, 1000));
+					assert_eq!((minted.to, minted.value), (accounts.alice, 10));
+					assert_eq!((transferred.from, transferred.to, transferred.value), (accounts.alice, accounts.bob, 5));
+					assert_eq!((burned.from, burned.value), (accounts.alice, 3));
+				}
+				_ => panic!("expected Minted, Minted, Transferred, Burned"),
+			}
+		}
 
-            assert_eq!(erc20.mint(10), true);
+		#[ink::test]
+        fn it_bridge_lock_and_unlock_work() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
 
-            assert_eq!(erc20.balance_of(accounts.alice), alice_balance + 10);
+			assert_eq!(erc20.bridge_lock(100, 7, vec![0xab; 20]), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 900);
+			assert_eq!(erc20.total_supply(), 900);
 
 			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events.len(), 2);
+            assert_eq!(emitted_events.len(), 3);
+			let decoded = <Event as scale::Decode>::decode(&mut &emitted_events[2].data[..]).unwrap();
+			match decoded {
+				Event::BridgeLocked(event) => {
+					assert_eq!(event.from, accounts.alice);
+					assert_eq!(event.amount, 100);
+					assert_eq!(event.dest_chain, 7);
+					assert_eq!(event.dest_account, vec![0xab; 20]);
+				}
+				_ => panic!("expected a BridgeLocked event"),
+			}
+
+			assert_eq!(erc20.set_bridge_relayer(Some(accounts.eve)), Ok(()));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.eve);
+			assert_eq!(erc20.bridge_unlock(accounts.bob, 100, 7, 1), Ok(()));
+			assert_eq!(erc20.bridge_unlock(accounts.bob, 100, 7, 1), Err(Error::BridgeNonceUsed));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.total_supply(), 1000);
 		}
 
 		#[ink::test]
-        fn it_burn_works() {
-    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string());
+        fn it_active_limits_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			assert_eq!(erc20.active_limits(), ActiveLimits {
+				max_mint_per_tx: None,
+				max_tx_amount: None,
+				max_wallet_balance: None,
+				cooldown_blocks: None,
+				mint_limit_per_period: None,
+				mint_period_length: None,
+				large_transfer_threshold: None,
+				large_transfer_delay: None,
+			});
+
+			assert_eq!(erc20.set_large_transfer_limit(100, 60), Ok(()));
+			assert_eq!(erc20.active_limits(), ActiveLimits {
+				max_mint_per_tx: None,
+				max_tx_amount: None,
+				max_wallet_balance: None,
+				cooldown_blocks: None,
+				mint_limit_per_period: None,
+				mint_period_length: None,
+				large_transfer_threshold: Some(100),
+				large_transfer_delay: Some(60),
+			});
+
+			assert_eq!(erc20.set_max_mint_per_tx(50), Ok(()));
+			assert_eq!(erc20.active_limits().max_mint_per_tx, Some(50));
+		}
+
+		#[ink::test]
+        fn it_event_schema_version_is_stable() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
 			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
 
-			let alice_balance = erc20.balance_of(accounts.alice);
+			let version = erc20.event_schema_version();
+			assert_ne!(version, 0);
+			assert_eq!(erc20.transfer(accounts.bob, 1), Ok(()));
+			assert_eq!(erc20.event_schema_version(), version);
+		}
 
-            assert_eq!(erc20.burn(10), true);
+		#[ink::test]
+        fn it_conditional_transfers_work() {
+			let contract = set_contract_account();
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+			let met = Hash::from([0x11; 32]);
+			let unmet = Hash::from([0x22; 32]);
+			let expiring = Hash::from([0x33; 32]);
+			assert_eq!(erc20.set_conditional_timeout(60), Ok(()));
 
-            assert_eq!(erc20.balance_of(accounts.alice), alice_balance - 10);
+			assert_eq!(erc20.create_conditional_transfer(accounts.bob, 100, accounts.frank, met), Ok(()));
+			assert_eq!(erc20.create_conditional_transfer(accounts.bob, 100, accounts.frank, unmet), Ok(()));
+			assert_eq!(erc20.create_conditional_transfer(accounts.bob, 100, accounts.frank, expiring), Ok(()));
+			assert_eq!(erc20.balance_of(contract), 300);
+			assert_eq!(erc20.reclaim_conditional(expiring), Err(Error::ConditionalTransferNotExpired));
+
+			// A met condition pays the recipient, an unmet one refunds the sender.
+			MET_CONDITIONS.with(|conditions| conditions.borrow_mut().push(met));
+			assert_eq!(erc20.resolve_conditional(accounts.alice, met), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.conditional_transfer(accounts.alice, met), None);
+			assert_eq!(erc20.resolve_conditional(accounts.alice, unmet), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.bob), 100);
+			assert_eq!(erc20.balance_of(accounts.alice), 800);
+
+			for _ in 0..10 {
+				ink_env::test::advance_block::<ink_env::DefaultEnvironment>();
+			}
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.reclaim_conditional(expiring), Err(Error::ConditionalTransferNotFound));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.reclaim_conditional(expiring), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 900);
+			assert_eq!(erc20.balance_of(contract), 0);
+
+			// Senders own their condition ids, so one cannot block another's.
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.create_conditional_transfer(accounts.charlie, 10, accounts.frank, met), Ok(()));
+			assert_eq!(erc20.create_conditional_transfer(accounts.charlie, 10, accounts.frank, met), Err(Error::ConditionInUse));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.create_conditional_transfer(accounts.charlie, 20, accounts.frank, met), Ok(()));
+			assert_eq!(erc20.resolve_conditional(accounts.alice, met), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.charlie), 20);
+			assert_eq!(erc20.conditional_transfer(accounts.bob, met).map(|conditional| conditional.amount), Some(10));
+		}
+
+		#[ink::test]
+        fn it_approve_and_transfer_from_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.approve(AccountId::from([0u8; 32]), 100), Err(Error::ZeroAddress));
+			assert_eq!(erc20.approve(accounts.bob, 100), Ok(()));
+			assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 100);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer_from(accounts.alice, accounts.charlie, 60), Ok(()));
+			assert_eq!(erc20.balance_of(accounts.alice), 940);
+			assert_eq!(erc20.balance_of(accounts.charlie), 60);
+			assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 40);
 
 			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
-            assert_eq!(emitted_events.len(), 2);
+            assert_eq!(emitted_events.len(), 4);
+		}
+
+		#[ink::test]
+        fn it_transfer_from_over_allowance_fails() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.approve(accounts.bob, 100), Ok(()));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.transfer_from(accounts.alice, accounts.charlie, 101), Err(Error::InsufficientAllowance));
+		}
+
+		#[ink::test]
+        fn it_failures_return_typed_errors() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.transfer(accounts.bob, 1001), Err(Error::InsufficientBalance));
+			assert_eq!(erc20.burn(1001), Err(Error::InsufficientBalance));
+			assert_eq!(erc20.balance_of(accounts.alice), 1000);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.mint(10), Err(Error::MissingRole));
+			assert_eq!(erc20.total_supply(), 1000);
+		}
+
+		#[ink::test]
+        fn it_psp22_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(PSP22::total_supply(&erc20), 1000);
+			assert_eq!(PSP22::transfer(&mut erc20, accounts.bob, 100, vec![]), Ok(()));
+			assert_eq!(PSP22::balance_of(&erc20, accounts.bob), 100);
+			assert_eq!(
+				PSP22::transfer(&mut erc20, accounts.bob, 1000, vec![]),
+				Err(PSP22Error::InsufficientBalance),
+			);
+
+			assert_eq!(PSP22::approve(&mut erc20, accounts.charlie, 50), Ok(()));
+			assert_eq!(PSP22::increase_allowance(&mut erc20, accounts.charlie, 20), Ok(()));
+			assert_eq!(PSP22::decrease_allowance(&mut erc20, accounts.charlie, 30), Ok(()));
+			assert_eq!(PSP22::allowance(&erc20, accounts.alice, accounts.charlie), 40);
+			assert_eq!(
+				PSP22::decrease_allowance(&mut erc20, accounts.charlie, 41),
+				Err(PSP22Error::InsufficientAllowance),
+			);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(PSP22::transfer_from(&mut erc20, accounts.alice, accounts.django, 40, vec![]), Ok(()));
+			assert_eq!(PSP22::balance_of(&erc20, accounts.django), 40);
+			assert_eq!(
+				PSP22::transfer_from(&mut erc20, accounts.alice, accounts.django, 1, vec![]),
+				Err(PSP22Error::InsufficientAllowance),
+			);
+		}
+
+		#[ink::test]
+        fn it_psp22_metadata_works() {
+    		let erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+
+			assert_eq!(erc20.name(), "Polkadot");
+			assert_eq!(erc20.token_symbol(), Some("DOT".to_string()));
+			assert_eq!(erc20.token_decimals(), 10);
+		}
+
+		#[ink::test]
+		#[should_panic(expected = "ERC20: too many decimals")]
+        fn it_constructor_rejects_too_many_decimals() {
+			Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), MAX_DECIMALS + 1);
+		}
+
+		#[ink::test]
+        fn it_two_step_ownership_transfer_works() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.transfer_ownership(accounts.bob), Ok(()));
+			assert_eq!(erc20.owner(), accounts.alice);
+			assert_eq!(erc20.pending_owner(), Some(accounts.bob));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.accept_ownership(), Err(Error::NotPendingOwner));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.accept_ownership(), Ok(()));
+			assert_eq!(erc20.owner(), accounts.bob);
+			assert_eq!(erc20.pending_owner(), None);
+			assert_eq!(erc20.mint(10), Err(Error::MissingRole));
+			assert_eq!(erc20.grant_role(MINTER_ROLE, accounts.bob), Ok(()));
+			assert_eq!(erc20.mint(10), Ok(()));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(erc20.grant_role(MINTER_ROLE, accounts.charlie), Err(Error::NotOwner));
+			assert_eq!(erc20.transfer_ownership(accounts.alice), Err(Error::NotOwner));
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 5);
+		}
+
+		#[ink::test]
+        #[cfg(feature = "pausable")]
+        fn it_pause_halts_token_movement() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert_eq!(erc20.pause(), Ok(()));
+			assert!(erc20.paused());
+			assert_eq!(erc20.pause(), Err(Error::Paused));
+			assert_eq!(erc20.transfer(accounts.bob, 1), Err(Error::Paused));
+			assert_eq!(erc20.mint(1), Err(Error::Paused));
+			assert_eq!(erc20.burn(1), Err(Error::Paused));
+
+			assert_eq!(erc20.unpause(), Ok(()));
+			assert!(!erc20.paused());
+			assert_eq!(erc20.unpause(), Err(Error::NotPaused));
+			assert_eq!(erc20.transfer(accounts.bob, 1), Ok(()));
+		}
+
+		#[ink::test]
+        fn it_pause_requires_pauser_role() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.pause(), Err(Error::MissingRole));
+			assert!(!erc20.paused());
+		}
+
+		#[ink::test]
+        #[cfg(feature = "pausable")]
+        fn it_roles_split_duties() {
+    		let mut erc20 = Erc20::new(1000, "Polkadot".to_string(), "DOT".to_string(), 10);
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			assert!(erc20.has_role(MINTER_ROLE, accounts.alice));
+			assert!(erc20.has_role(BURNER_ROLE, accounts.alice));
+			assert!(erc20.has_role(PAUSER_ROLE, accounts.alice));
+
+			assert_eq!(erc20.grant_role(MINTER_ROLE, accounts.bob), Ok(()));
+			assert_eq!(erc20.grant_role(PAUSER_ROLE, accounts.charlie), Ok(()));
+			assert_eq!(erc20.revoke_role(MINTER_ROLE, accounts.alice), Ok(()));
+			assert!(!erc20.has_role(MINTER_ROLE, accounts.alice));
+			assert_eq!(erc20.mint(1), Err(Error::MissingRole));
+			assert_eq!(erc20.mintable_remaining_for(accounts.alice), 0);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			assert_eq!(erc20.mint(10), Ok(()));
+			assert_eq!(erc20.burn(1), Err(Error::MissingRole));
+			assert_eq!(erc20.pause(), Err(Error::MissingRole));
+			assert_eq!(erc20.grant_role(BURNER_ROLE, accounts.bob), Err(Error::NotOwner));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.charlie);
+			assert_eq!(erc20.pause(), Ok(()));
+			assert_eq!(erc20.unpause(), Ok(()));
+
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			// Constructor transfer, two grants, one revoke, the mint, pause and unpause.
+			assert_eq!(emitted_events.len(), 7);
 		}
     }
 }
+
+/// Deploys tokens from the uploaded code of the `erc20` contract. A crate builds into one contract, so this
+/// one is built instead of the token with the `factory` feature.
+#[cfg(feature = "factory")]
+#[ink::contract]
+pub mod factory {
+	#[cfg(not(test))]
+	use ink_env::call::{build_create, ExecutionInput, FromAccountId, Selector};
+	use ink_prelude::{string::String, vec::Vec};
+	use ink_storage::{traits::SpreadAllocate, Mapping};
+
+	#[ink(event)]
+	pub struct TokenCreated {
+		#[ink(topic)]
+		token: AccountId,
+		#[ink(topic)]
+		creator: AccountId,
+	}
+
+	/// The error types returned by the factory's messages.
+	#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub enum Error {
+		/// The caller is not the factory owner.
+		NotOwner,
+		/// The token constructor failed or the salt was already used with this code hash.
+		InstantiationFailed,
+	}
+
+	/// The result type returned by the factory's messages.
+	pub type Result<T> = core::result::Result<T, Error>;
+
+	/// The account a token was instantiated at.
+	#[cfg(not(test))]
+	struct TokenAccount(AccountId);
+
+	#[cfg(not(test))]
+	impl FromAccountId<Environment> for TokenAccount {
+		fn from_account_id(account_id: AccountId) -> Self {
+			Self(account_id)
+		}
+	}
+
+	#[ink(storage)]
+	#[derive(SpreadAllocate)]
+	pub struct Factory {
+		_owner: AccountId,
+		_token_code_hash: Hash,
+		/// Every token created, in order of creation.
+		_tokens: Mapping<u32, AccountId>,
+		_token_count: u32,
+	}
+
+	impl Factory {
+		/// `token_code_hash` is the hash the `erc20` contract's code was uploaded under.
+		#[ink(constructor)]
+		pub fn new(token_code_hash: Hash) -> Self {
+			ink_lang::utils::initialize_contract(|contract: &mut Self| {
+				contract._owner = Self::env().caller();
+				contract._token_code_hash = token_code_hash;
+			})
+		}
+
+		#[ink(message)]
+		pub fn owner(&self) -> AccountId {
+			self._owner
+		}
+
+		#[ink(message)]
+		pub fn token_code_hash(&self) -> Hash {
+			self._token_code_hash
+		}
+
+		/// Points future `create_token` calls at new token code; existing tokens are unaffected.
+		#[ink(message)]
+		pub fn set_token_code_hash(&mut self, code_hash: Hash) -> Result<()> {
+			if self.env().caller() != self._owner {
+				return Err(Error::NotOwner);
+			}
+			self._token_code_hash = code_hash;
+
+			Ok(())
+		}
+
+		/// Instantiates a token through its `new_for` constructor, handing supply and ownership to the
+		/// caller. The address only depends on the factory, code hash and `salt`, so it is known up front.
+		#[ink(message)]
+		pub fn create_token(&mut self, total_supply: Balance, name: String, symbol: String, decimals: u8, salt: Vec<u8>) -> Result<AccountId> {
+			let creator = self.env().caller();
+			let token = self._instantiate_token(creator, total_supply, name, symbol, decimals, salt)?;
+			self._tokens.insert(self._token_count, &token);
+			self._token_count += 1;
+
+			Self::env().emit_event(TokenCreated { token, creator });
+
+			Ok(token)
+		}
+
+		#[ink(message)]
+		pub fn token_count(&self) -> u32 {
+			self._token_count
+		}
+
+		/// Up to `limit` created tokens, starting at position `offset`.
+		#[ink(message)]
+		pub fn tokens(&self, offset: u32, limit: u32) -> Vec<AccountId> {
+			let end = offset.saturating_add(limit).min(self._token_count);
+			(offset..end).filter_map(|index| self._tokens.get(index)).collect()
+		}
+
+		#[cfg(not(test))]
+		fn _instantiate_token(
+			&self,
+			owner: AccountId,
+			total_supply: Balance,
+			name: String,
+			symbol: String,
+			decimals: u8,
+			salt: Vec<u8>,
+		) -> Result<AccountId> {
+			build_create::<Environment, TokenAccount>()
+				.code_hash(self._token_code_hash)
+				.gas_limit(0)
+				.endowment(0)
+				.exec_input(
+					ExecutionInput::new(Selector::new(ink_lang::selector_bytes!("new_for")))
+						.push_arg(owner)
+						.push_arg(total_supply)
+						.push_arg(name)
+						.push_arg(symbol)
+						.push_arg(decimals),
+				)
+				.salt_bytes(salt)
+				.params()
+				.instantiate()
+				.map(|TokenAccount(token)| token)
+				.map_err(|_| Error::InstantiationFailed)
+		}
+
+		/// The off-chain environment cannot instantiate contracts, tests get an address derived from the
+		/// code hash and salt, and a used salt fails like a taken address would.
+		#[cfg(test)]
+		fn _instantiate_token(
+			&self,
+			_owner: AccountId,
+			_total_supply: Balance,
+			_name: String,
+			_symbol: String,
+			_decimals: u8,
+			salt: Vec<u8>,
+		) -> Result<AccountId> {
+			let mut address = [0u8; 32];
+			ink_env::hash_encoded::<ink_env::hash::Blake2x256, _>(&(self._token_code_hash, salt), &mut address);
+			let token = AccountId::from(address);
+			if self.tokens(0, self._token_count).contains(&token) {
+				return Err(Error::InstantiationFailed);
+			}
+
+			Ok(token)
+		}
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+		use ink_lang as ink;
+
+		#[ink::test]
+		fn it_create_token_tracks_deployed_tokens() {
+			let mut factory = Factory::new(Hash::from([0x01; 32]));
+			let accounts = ink_env::test::default_accounts::<ink_env::DefaultEnvironment>();
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.bob);
+			let first = factory.create_token(1000, "Polkadot".to_string(), "DOT".to_string(), 10, vec![1]).unwrap();
+			assert_eq!(
+				factory.create_token(1000, "Polkadot".to_string(), "DOT".to_string(), 10, vec![1]),
+				Err(Error::InstantiationFailed)
+			);
+			let second = factory.create_token(50, "Kusama".to_string(), "KSM".to_string(), 12, vec![2]).unwrap();
+			assert_ne!(first, second);
+			assert_eq!(factory.token_count(), 2);
+			assert_eq!(factory.tokens(0, 10), vec![first, second]);
+			assert_eq!(factory.tokens(1, 1), vec![second]);
+			assert_eq!(ink_env::test::recorded_events().count(), 2);
+
+			assert_eq!(factory.set_token_code_hash(Hash::from([0x02; 32])), Err(Error::NotOwner));
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(accounts.alice);
+			assert_eq!(factory.set_token_code_hash(Hash::from([0x02; 32])), Ok(()));
+			assert_eq!(factory.token_code_hash(), Hash::from([0x02; 32]));
+		}
+	}
+}